@@ -0,0 +1,80 @@
+//! Per-poll latency of reading the branch of 20 worktree sessions:
+//! spawning `git rev-parse --abbrev-ref HEAD` per session (the old implementation)
+//! versus reading HEAD in-process through libgit2.
+//!
+//! Run with `cargo bench --bench git_poll`.
+
+use clutch_desktop_lib::get_branch;
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const SESSIONS: usize = 20;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args([
+            "-c",
+            "user.name=bench",
+            "-c",
+            "user.email=bench@example.com",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("failed to run git")
+        .status;
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+/// A repo with `SESSIONS` linked worktrees, one per simulated session.
+fn setup_worktrees() -> (PathBuf, Vec<String>) {
+    let root = std::env::temp_dir().join(format!("clutch-git-poll-{}", std::process::id()));
+    let repo = root.join("repo");
+    std::fs::create_dir_all(&repo).unwrap();
+    git(&repo, &["init", "-q"]);
+    git(&repo, &["commit", "-q", "--allow-empty", "-m", "init"]);
+
+    let dirs = (0..SESSIONS)
+        .map(|i| {
+            let wt = root.join(format!("session-{}", i));
+            let branch = format!("session-{}", i);
+            git(
+                &repo,
+                &["worktree", "add", "-q", "-b", &branch, wt.to_str().unwrap()],
+            );
+            wt.to_string_lossy().to_string()
+        })
+        .collect();
+    (root, dirs)
+}
+
+fn branch_via_cli(dir: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn bench_git_poll(c: &mut Criterion) {
+    let (root, dirs) = setup_worktrees();
+
+    let mut group = c.benchmark_group(format!("poll_{}_sessions", SESSIONS));
+    group.bench_function("git_cli", |b| {
+        b.iter(|| dirs.iter().filter_map(|d| branch_via_cli(d)).count())
+    });
+    group.bench_function("libgit2", |b| {
+        b.iter(|| dirs.iter().filter_map(|d| get_branch(d)).count())
+    });
+    group.finish();
+
+    let _ = std::fs::remove_dir_all(root);
+}
+
+criterion_group!(benches, bench_git_poll);
+criterion_main!(benches);
@@ -0,0 +1,219 @@
+use crate::git_watcher::GitWatcher;
+use crate::permissions::Decision;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// What the agent in a session is doing, derived from its hook events.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionState {
+    /// Started, no prompt yet.
+    #[default]
+    Idle,
+    Working,
+    /// Blocked on a tool permission prompt.
+    WaitingPermission,
+    /// Finished its turn (or nudged the user after idling); waiting for the next prompt.
+    WaitingInput,
+    Compacting,
+    /// Claude exited, or the conversation was cleared (a `SessionStart` follows then).
+    Ended,
+}
+
+/// Folds hook events into a `SessionState`.
+#[derive(Debug, Default, Clone)]
+pub struct SessionStateMachine {
+    state: SessionState,
+    /// Where to return once compaction finishes: auto-compaction happens mid-turn,
+    /// `/compact` while waiting for input.
+    before_compact: SessionState,
+}
+
+impl SessionStateMachine {
+    pub fn apply(&mut self, activity: &SessionActivity) -> SessionState {
+        let message = activity.message.as_deref().unwrap_or_default();
+        self.state = match activity.event.as_str() {
+            "SessionStart" if self.state == SessionState::Compacting => self.before_compact,
+            "SessionStart" => SessionState::Idle,
+            "UserPromptSubmit" | "PreToolUse" | "PostToolUse" => SessionState::Working,
+            "PermissionRequest" => match activity.permission_decision {
+                // Answered by the repo's policy; Claude carries on without prompting
+                Some(Decision::Allow | Decision::Deny) => SessionState::Working,
+                _ => SessionState::WaitingPermission,
+            },
+            "Notification" => match activity.notification_type.as_deref() {
+                Some("permission_prompt") => SessionState::WaitingPermission,
+                Some("idle_prompt") => SessionState::WaitingInput,
+                // Older Claude Code versions only send the message
+                None if message.contains("permission") => SessionState::WaitingPermission,
+                None if message.contains("waiting for your input") => SessionState::WaitingInput,
+                _ => self.state,
+            },
+            "Stop" => SessionState::WaitingInput,
+            "PreCompact" => {
+                if self.state != SessionState::Compacting {
+                    self.before_compact = self.state;
+                }
+                SessionState::Compacting
+            }
+            "SessionEnd" => SessionState::Ended,
+            // SubagentStop, TaskCompleted: the main agent carries on
+            _ => self.state,
+        };
+        self.state
+    }
+}
+
+/// A hook event from a session's `status` file, with the payload fields the UI uses.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SessionActivity {
+    /// Hook event name, e.g. `UserPromptSubmit`, `PreToolUse`, `Stop`.
+    pub event: String,
+    /// Unix timestamp (seconds) when the hook ran.
+    pub timestamp: i64,
+    /// Session state after this event.
+    #[serde(default)]
+    pub state: SessionState,
+    /// Tool about to run or asking for permission (`PreToolUse`, `PermissionRequest`).
+    pub tool_name: Option<String>,
+    /// The tool's shell command (`Bash`), if any.
+    #[serde(default)]
+    pub command: Option<String>,
+    /// The file the tool reads or edits, if any.
+    #[serde(default)]
+    pub file_path: Option<String>,
+    /// Text shown to the user (`Notification`).
+    pub message: Option<String>,
+    /// Why a `Notification` was sent, e.g. `permission_prompt` or `idle_prompt`.
+    #[serde(default)]
+    pub notification_type: Option<String>,
+    /// How the permission policy answered a `PermissionRequest`.
+    #[serde(default)]
+    pub permission_decision: Option<Decision>,
+    /// Claude's own session ID, unrelated to Clutch's.
+    pub claude_session_id: Option<String>,
+    pub transcript_path: Option<String>,
+    pub cwd: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct SessionActivityPayload {
+    pub session_id: String,
+    #[serde(flatten)]
+    pub activity: SessionActivity,
+}
+
+/// Push an event to the frontend as `session-activity`. A tool call or finished turn
+/// may have edited files, so it also has the session's git state re-read.
+pub fn emit_activity(app: &AppHandle, session_id: String, activity: SessionActivity) {
+    if matches!(activity.event.as_str(), "PostToolUse" | "Stop") {
+        if let Some(watcher) = app.try_state::<GitWatcher>() {
+            watcher.refresh(&session_id);
+        }
+    }
+    let _ = app.emit(
+        "session-activity",
+        SessionActivityPayload {
+            session_id,
+            activity,
+        },
+    );
+}
+
+/// One hook event as sent by `clutch --hook` (see hook_socket.rs), and as a line
+/// of the status file it falls back to.
+#[derive(Serialize, Deserialize)]
+pub struct StatusRecord {
+    pub event: String,
+    pub timestamp: i64,
+    #[serde(default)]
+    pub payload: Option<HookPayload>,
+    /// Set when the permission policy answered a `PermissionRequest` itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permission_decision: Option<Decision>,
+}
+
+/// The subset of Claude's hook stdin JSON that is kept; other fields are dropped.
+#[derive(Serialize, Deserialize, Default)]
+pub struct HookPayload {
+    session_id: Option<String>,
+    transcript_path: Option<String>,
+    cwd: Option<String>,
+    tool_name: Option<String>,
+    #[serde(default)]
+    tool_input: Option<ToolInput>,
+    message: Option<String>,
+    notification_type: Option<String>,
+}
+
+/// The parts of a tool's input shown with a permission request.
+#[derive(Serialize, Deserialize, Default)]
+pub struct ToolInput {
+    command: Option<String>,
+    #[serde(alias = "notebook_path")]
+    file_path: Option<String>,
+}
+
+impl From<StatusRecord> for SessionActivity {
+    fn from(record: StatusRecord) -> Self {
+        let payload = record.payload.unwrap_or_default();
+        let tool_input = payload.tool_input.unwrap_or_default();
+        SessionActivity {
+            event: record.event,
+            timestamp: record.timestamp,
+            state: SessionState::default(),
+            tool_name: payload.tool_name,
+            command: tool_input.command,
+            file_path: tool_input.file_path,
+            message: payload.message,
+            notification_type: payload.notification_type,
+            permission_decision: record.permission_decision,
+            claude_session_id: payload.session_id,
+            transcript_path: payload.transcript_path,
+            cwd: payload.cwd,
+        }
+    }
+}
+
+/// Parse one status line; malformed lines (e.g. a hook killed mid-write) yield `None`.
+pub fn parse_status_line(line: &str) -> Option<SessionActivity> {
+    let record: StatusRecord = serde_json::from_str(line.trim()).ok()?;
+    Some(record.into())
+}
+
+/// Records appended to `path` since byte `offset`, and the offset to resume from.
+pub fn read_new_records(path: &Path, offset: u64) -> (Vec<SessionActivity>, u64) {
+    let (lines, offset) = read_new_lines(path, offset);
+    let records = lines.iter().filter_map(|l| parse_status_line(l)).collect();
+    (records, offset)
+}
+
+/// Lines appended to a JSONL file since byte `offset`, and the offset to resume from.
+/// Only complete lines are consumed, so a line being written is picked up next time.
+/// A file shorter than `offset` was recreated and is read from the start.
+pub fn read_new_lines(path: &Path, offset: u64) -> (Vec<String>, u64) {
+    let Ok(mut file) = std::fs::File::open(path) else {
+        return (Vec::new(), offset);
+    };
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let start = if len < offset { 0 } else { offset };
+    if len == start || file.seek(SeekFrom::Start(start)).is_err() {
+        return (Vec::new(), start);
+    }
+
+    let mut bytes = Vec::new();
+    if file.read_to_end(&mut bytes).is_err() {
+        return (Vec::new(), start);
+    }
+    let Some(end) = bytes.iter().rposition(|&b| b == b'\n') else {
+        return (Vec::new(), start);
+    };
+
+    let lines = String::from_utf8_lossy(&bytes[..end])
+        .lines()
+        .map(str::to_string)
+        .collect();
+    (lines, start + end as u64 + 1)
+}
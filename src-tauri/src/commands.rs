@@ -1,14 +1,22 @@
 use crate::git;
 use crate::notifications::SessionsDir;
 use crate::pty::PtyManager;
+use crate::resume;
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, State};
+use tauri::{AppHandle, Emitter, State};
 
 pub struct PtyState(pub Mutex<HashMap<String, PtyManager>>);
 
 pub struct WorktreeRegistry(pub Mutex<HashSet<String>>);
 
+#[derive(Clone, serde::Serialize)]
+pub struct WorktreeScriptOutputPayload {
+    pub worktree_id: String,
+    pub stage: &'static str,
+    pub line: String,
+}
+
 #[tauri::command]
 pub fn create_session(
     state: State<'_, PtyState>,
@@ -32,6 +40,9 @@ pub fn create_session(
 
     // Create session directory for status tracking
     sessions_dir.create_session_dir(&session_id);
+    if let Some(dir) = &working_dir {
+        sessions_dir.track_checkpoints(&session_id, dir);
+    }
 
     let env_vars = vec![
         ("CLUTCH_SESSION_ID".to_string(), session_id.clone()),
@@ -59,12 +70,15 @@ pub fn destroy_session(
     // Removing from the map drops PtyManager, which closes the PTY
     map.remove(&session_id);
 
-    // Clean up session directory
+    // Clean up session directory and per-prompt checkpoints
+    sessions_dir.discard_checkpoints(&session_id);
     sessions_dir.remove_session_dir(&session_id);
 
     Ok(())
 }
 
+/// With `resume`, relaunches the session's last Claude conversation (`claude --resume`)
+/// in the same working dir instead of running `command`.
 #[tauri::command]
 pub fn restart_session(
     state: State<'_, PtyState>,
@@ -75,7 +89,17 @@ pub fn restart_session(
     rows: u16,
     working_dir: Option<String>,
     command: Option<String>,
+    resume: Option<bool>,
 ) -> Result<(), String> {
+    let command = if resume.unwrap_or(false) {
+        let claude_session = sessions_dir
+            .claude_session(&session_id)
+            .ok_or_else(|| "No Claude conversation to resume".to_string())?;
+        Some(resume::resume_command(&claude_session)?)
+    } else {
+        command
+    };
+
     // Destroy existing
     {
         let mut map = state
@@ -111,37 +135,100 @@ pub fn session_write(
     Ok(())
 }
 
+/// Branch name for a new session's worktree from the user's template (see
+/// `git::generate_branch_name` for placeholders), unique among the repo's branches.
+#[tauri::command]
+pub fn generate_branch_name(
+    working_dir: String,
+    template: String,
+    description: Option<String>,
+    ticket: Option<String>,
+) -> Result<String, String> {
+    git::generate_branch_name(
+        &working_dir,
+        &template,
+        description.as_deref(),
+        ticket.as_deref(),
+    )
+}
+
+/// Runs on a blocking thread: submodule clones and the repo's setup script may take
+/// minutes (e.g. `npm install`). Their output is streamed as `worktree-script-output`.
+/// `sparse_paths` (directories) gives a sparse-checkout worktree for large monorepos.
 #[tauri::command]
-pub fn setup_session_worktree(
+pub async fn setup_session_worktree(
     registry: State<'_, WorktreeRegistry>,
+    app_handle: AppHandle,
     worktree_id: String,
     branch_name: String,
     working_dir: String,
     location: String,
-) -> git::WorktreeSetupResult {
-    let result = git::setup_worktree_for_session(&working_dir, &branch_name, &location);
+    source: Option<git::WorktreeSource>,
+    sparse_paths: Option<Vec<String>>,
+    failure_policy: Option<git::WorktreeFailurePolicy>,
+    init_submodules: Option<bool>,
+) -> Result<git::WorktreeSetupResult, git::WorktreeSetupError> {
+    let id = worktree_id.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let mut on_output =
+            |stage: &'static str, line: &str| emit_script_output(&app_handle, &id, stage, line);
+        let source = source.unwrap_or_default();
+        let sparse_dirs = sparse_paths.unwrap_or_default();
+        let options = git::WorktreeOptions {
+            location: &location,
+            source: &source,
+            sparse_dirs: &sparse_dirs,
+            policy: failure_policy.unwrap_or_default(),
+            init_submodules: init_submodules.unwrap_or(true),
+        };
+        git::setup_worktree_for_session(&working_dir, &branch_name, &id, &options, &mut on_output)
+    })
+    .await
+    .map_err(|e| git::WorktreeError::Io {
+        message: format!("Worktree setup task failed: {}", e),
+    })??;
+
     if result.worktree_path.is_some() {
         if let Ok(mut set) = registry.0.lock() {
             set.insert(worktree_id);
         }
     }
-    result
+    Ok(result)
 }
 
 #[tauri::command]
-pub fn cleanup_session_worktree(
+pub async fn cleanup_session_worktree(
     registry: State<'_, WorktreeRegistry>,
+    app_handle: AppHandle,
     worktree_id: String,
     worktree_path: String,
     git_repo_path: String,
-) -> git::WorktreeRemoveResult {
-    let result = git::remove_worktree(&git_repo_path, &worktree_path);
+) -> Result<git::WorktreeRemoveResult, String> {
+    let id = worktree_id.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let mut on_output = |line: &str| emit_script_output(&app_handle, &id, "teardown", line);
+        git::teardown_and_remove_worktree(&git_repo_path, &worktree_path, &mut on_output)
+    })
+    .await
+    .map_err(|e| format!("Worktree cleanup task failed: {}", e))?;
+
     if result.success {
         if let Ok(mut set) = registry.0.lock() {
             set.remove(&worktree_id);
         }
     }
-    result
+    Ok(result)
+}
+
+fn emit_script_output(app_handle: &AppHandle, worktree_id: &str, stage: &'static str, line: &str) {
+    let _ = app_handle.emit(
+        "worktree-script-output",
+        WorktreeScriptOutputPayload {
+            worktree_id: worktree_id.to_string(),
+            stage,
+            line: line.to_string(),
+        },
+    );
 }
 
 #[tauri::command]
@@ -152,6 +239,52 @@ pub fn validate_worktrees(worktree_paths: Vec<String>) -> Vec<bool> {
         .collect()
 }
 
+/// List the repo's worktrees, flagging Clutch-created ones that no open tab refers to.
+#[tauri::command]
+pub fn scan_worktrees(
+    repo: String,
+    known_paths: Vec<String>,
+) -> Result<git::WorktreeScan, String> {
+    git::scan_worktrees(&repo, &known_paths)
+}
+
+/// Take ownership of an existing worktree so the frontend can open it as a new session.
+#[tauri::command]
+pub fn adopt_worktree(
+    registry: State<'_, WorktreeRegistry>,
+    worktree_id: String,
+    worktree_path: String,
+    git_repo_path: String,
+) -> Result<git::WorktreeSetupResult, String> {
+    if !git::validate_worktree_path(&worktree_path) {
+        return Err(format!("Worktree not found: {}", worktree_path));
+    }
+    git::mark_clutch_worktree(&worktree_path, &worktree_id, None)?;
+    let _ = git::lock_worktree(&git_repo_path, &worktree_path, &worktree_id);
+    if let Ok(mut set) = registry.0.lock() {
+        set.insert(worktree_id);
+    }
+    Ok(git::WorktreeSetupResult {
+        effective_dir: worktree_path.clone(),
+        branch_name: git::get_branch(&worktree_path).filter(|b| b != "HEAD"),
+        worktree_path: Some(worktree_path),
+        git_repo_path: Some(git_repo_path),
+        error: None,
+        included_files: Vec::new(),
+        submodules: None,
+        lfs: None,
+        setup: None,
+    })
+}
+
+#[tauri::command]
+pub fn prune_worktrees(
+    git_repo_path: String,
+    worktree_paths: Vec<String>,
+) -> Vec<git::WorktreeRemoveResult> {
+    git::prune_worktrees(&git_repo_path, &worktree_paths)
+}
+
 #[tauri::command]
 pub fn session_resize(
     state: State<'_, PtyState>,
@@ -173,17 +306,82 @@ pub fn session_resize(
     Ok(())
 }
 
+/// Checkpoints taken on each prompt of the session, oldest first.
+#[tauri::command]
+pub fn list_checkpoints(
+    session_id: String,
+    working_dir: String,
+) -> Result<Vec<git::Checkpoint>, String> {
+    git::list_checkpoints(&working_dir, &session_id)
+}
+
+/// Roll the session's worktree back to the state before prompt `index`.
+/// Returns the checkpoint of the state that was replaced.
 #[tauri::command]
-pub fn get_git_branches(
+pub async fn restore_checkpoint(
+    session_id: String,
+    working_dir: String,
+    index: u32,
+) -> Result<git::Checkpoint, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        git::restore_checkpoint(&working_dir, &session_id, index)
+    })
+    .await
+    .map_err(|e| format!("Checkpoint restore failed: {}", e))?
+}
+
+/// Unified diff from checkpoint `index` to the session's current worktree.
+#[tauri::command]
+pub async fn diff_checkpoint(
+    session_id: String,
+    working_dir: String,
+    index: u32,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        git::diff_checkpoint(&working_dir, &session_id, index)
+    })
+    .await
+    .map_err(|e| format!("Checkpoint diff failed: {}", e))?
+}
+
+/// Apply commits or uncommitted changes from one session's worktree to another's.
+#[tauri::command]
+pub async fn transfer_changes(
+    source_dir: String,
+    target_dir: String,
+    source: git::TransferSource,
+) -> Result<git::TransferResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        git::transfer_changes(&source_dir, &target_dir, &source)
+    })
+    .await
+    .map_err(|e| format!("Transfer failed: {}", e))
+}
+
+/// Write the session branch's commits since it forked as a patch series or bundle,
+/// so work can be handed off without pushing.
+#[tauri::command]
+pub async fn export_session_changes(
+    working_dir: String,
+    format: git::ExportFormat,
+    output_path: String,
+) -> Result<git::ExportResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        git::export_session_changes(&working_dir, format, &output_path)
+    })
+    .await
+    .map_err(|e| format!("Export failed: {}", e))?
+}
+
+/// Files edited by more than one session of the same repo (session_id -> working_dir),
+/// so parallel agents stepping on each other show up before merge time.
+#[tauri::command]
+pub async fn detect_worktree_overlaps(
     sessions: HashMap<String, String>,
-) -> HashMap<String, String> {
-    let mut result = HashMap::new();
-    for (session_id, working_dir) in sessions {
-        if let Some(branch) = git::get_branch(&working_dir) {
-            result.insert(session_id, branch);
-        }
-    }
-    result
+) -> Result<Vec<git::SessionOverlap>, String> {
+    tauri::async_runtime::spawn_blocking(move || git::detect_overlaps(&sessions))
+        .await
+        .map_err(|e| format!("Overlap check failed: {}", e))
 }
 
 /// Clean up all active PTYs and session dirs — called on app exit.
@@ -1,79 +1,204 @@
+mod branch_name;
+mod checkout;
+mod checkpoint;
+mod error;
+mod export;
+mod include;
+mod overlap;
+mod scan;
+mod scripts;
+mod sparse;
+mod submodules;
+mod transfer;
+
+pub use branch_name::generate_branch_name;
+pub use checkout::WorktreeSource;
+pub use checkpoint::{
+    create_checkpoint, delete_checkpoints, diff_checkpoint, list_checkpoints, restore_checkpoint,
+    Checkpoint,
+};
+pub use error::{WorktreeError, WorktreeFailurePolicy, WorktreeSetupError};
+pub use export::{export_session_changes, ExportFormat, ExportResult};
+pub use overlap::{detect_overlaps, SessionOverlap};
+pub use scan::{
+    lock_worktree, mark_clutch_worktree, prune_worktrees, scan_worktrees, WorktreeScan,
+};
+pub use scripts::ScriptResult;
+pub use transfer::{transfer_changes, TransferResult, TransferSource};
+
+use git2::Repository;
 use serde::Serialize;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Attempts (including the first) made under `WorktreeFailurePolicy::Retry`.
+const MAX_WORKTREE_ATTEMPTS: u32 = 5;
+
+/// How `setup_worktree_for_session` creates and prepares a worktree.
+pub struct WorktreeOptions<'a> {
+    /// "sibling", "home" or a custom path (see `create_worktree`).
+    pub location: &'a str,
+    pub source: &'a WorktreeSource,
+    /// Cone directories for a sparse checkout; empty checks out everything.
+    pub sparse_dirs: &'a [String],
+    pub policy: WorktreeFailurePolicy,
+    /// Check out submodules and LFS files before the setup script runs.
+    pub init_submodules: bool,
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct WorktreeSetupResult {
     pub effective_dir: String,
     pub worktree_path: Option<String>,
     pub git_repo_path: Option<String>,
+    /// Branch the worktree is on; may differ from the requested one after a retry.
+    pub branch_name: Option<String>,
+    /// Why worktree creation failed, when `effective_dir` fell back to the original dir.
+    pub error: Option<WorktreeSetupError>,
+    /// Ignored files copied or linked in from `.clutch/worktree-include`.
+    pub included_files: Vec<String>,
+    /// Outcome of `git submodule update`, if the repo has submodules.
+    pub submodules: Option<ScriptResult>,
+    /// Outcome of `git lfs pull`, if the repo uses LFS.
+    pub lfs: Option<ScriptResult>,
+    /// Outcome of `.clutch/worktree-setup`, if the repo has one.
+    pub setup: Option<ScriptResult>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct GitState {
+    pub branch: Option<String>,
+    /// Commit HEAD points at; `None` on an unborn branch.
+    pub head: Option<String>,
+    /// Uncommitted changes or untracked files (ignored files don't count).
+    pub dirty: bool,
 }
 
 #[derive(Debug, Serialize, Clone)]
 pub struct WorktreeRemoveResult {
     pub success: bool,
     pub error: Option<String>,
+    /// Outcome of `.clutch/worktree-teardown`, if the repo has one.
+    pub teardown: Option<ScriptResult>,
 }
 
-/// Run `git rev-parse --show-toplevel` to find the repo root, if any.
+/// Open the repository containing `dir`, searching parent directories.
+///
+/// Read-only queries go through libgit2 rather than spawning `git`, since
+/// git state is re-read for every session on each watcher event. Worktree management
+/// still shells out to the CLI, which libgit2 can't fully replace.
+fn open_repo(dir: &str) -> Option<Repository> {
+    Repository::discover(dir).ok()
+}
+
+/// The commit where the worktree's HEAD forked from the main checkout's HEAD
+/// (HEAD itself in the main checkout).
+fn fork_point(repo: &Repository) -> Option<git2::Oid> {
+    let head = repo.head().ok()?.peel_to_commit().ok()?.id();
+    let main_head = Repository::open(repo.commondir())
+        .and_then(|main| main.head()?.peel_to_commit().map(|commit| commit.id()))
+        .unwrap_or(head);
+    Some(repo.merge_base(head, main_head).unwrap_or(head))
+}
+
+/// Find the root of the working tree containing `dir`, if any.
 pub fn find_git_root(dir: &str) -> Option<String> {
+    let repo = open_repo(dir)?;
+    let workdir = repo.workdir()?;
+    let root = workdir.to_string_lossy();
+    Some(root.trim_end_matches(['/', '\\']).to_string())
+}
+
+/// Find the main checkout of the repo containing `dir`. For a linked worktree this is
+/// where the repo's `.clutch/` files live, even when they aren't committed.
+pub fn find_main_checkout(dir: &str) -> Option<String> {
+    let repo = open_repo(dir)?;
+    let main = Repository::open(repo.commondir()).ok()?;
+    let root = main.workdir()?.to_string_lossy().to_string();
+    Some(root.trim_end_matches(['/', '\\']).to_string())
+}
+
+/// Run a git command in `dir` and return its trimmed stdout, or stderr on failure.
+fn run_git(dir: &str, args: &[&str]) -> Result<String, String> {
+    run_git_with_env(dir, args, &[])
+}
+
+fn run_git_with_env(dir: &str, args: &[&str], envs: &[(&str, &str)]) -> Result<String, String> {
     let output = Command::new("git")
-        .args(["rev-parse", "--show-toplevel"])
+        .args(args)
+        .envs(envs.iter().copied())
         .current_dir(dir)
         .output()
-        .ok()?;
+        .map_err(|e| format!("Failed to run git {}: {}", args.join(" "), e))?;
 
     if output.status.success() {
-        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
     } else {
-        None
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!("git {} failed: {}", args.join(" "), stderr.trim()))
     }
 }
 
-/// Create a worktree with the given branch name.
+/// Create a worktree checking out `source`. For `WorktreeSource::NewBranch`
+/// a branch named `branch_name` is created; other sources name the folder after
+/// the branch or commit they check out.
 ///
 /// `location` is one of: "sibling", "home", or an absolute custom path.
 /// - "sibling": creates `{repo_root}-{branch_name}` next to the repo
 /// - "home": creates `~/.clutch/worktrees/{repo_name}/{branch_name}`
 /// - custom path: creates `{custom_path}/{repo_name}/{branch_name}`
+///
+/// A non-empty `sparse_dirs` creates the worktree with `--no-checkout` and then checks
+/// out only those directories (cone-mode sparse checkout), for large monorepos.
+///
+/// The worktree is locked for `session_id` so `git worktree prune` elsewhere can't
+/// remove it while the session runs; `remove_worktree` unlocks it. It is also marked
+/// as Clutch's, recording the branch if one was created (see `scan::mark_clutch_worktree`).
 pub fn create_worktree(
     repo_root: &str,
     branch_name: &str,
     location: &str,
-) -> Result<String, String> {
+    source: &WorktreeSource,
+    sparse_dirs: &[String],
+    session_id: &str,
+) -> Result<String, WorktreeError> {
     let repo_path = Path::new(repo_root);
     let repo_name = repo_path
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("repo");
 
-    // Use branch name as folder name, replacing `/` with `-` for filesystem safety
-    let folder_name = branch_name.replace('/', "-");
+    let checkout = checkout::resolve_checkout(repo_root, source, branch_name)?;
+    let sparse_dirs = sparse::normalize_cone(sparse_dirs);
+    let folder_name = &checkout.folder_name;
 
     let worktree_path = match location {
         "sibling" => {
             let parent = repo_path
                 .parent()
-                .ok_or_else(|| "Cannot determine parent directory of repo".to_string())?;
+                .ok_or_else(|| WorktreeError::Io {
+                    message: "Cannot determine parent directory of repo".to_string(),
+                })?;
             parent
-                .join(&folder_name)
+                .join(folder_name)
                 .to_string_lossy()
                 .to_string()
         }
         "home" => {
-            let home = crate::config::home_dir()?;
+            let home =
+                crate::config::home_dir().map_err(|message| WorktreeError::Io { message })?;
             Path::new(&home)
                 .join(crate::config::base_dir_name())
                 .join("worktrees")
                 .join(repo_name)
-                .join(&folder_name)
+                .join(folder_name)
                 .to_string_lossy()
                 .to_string()
         }
         custom => {
             Path::new(custom)
                 .join(repo_name)
-                .join(&folder_name)
+                .join(folder_name)
                 .to_string_lossy()
                 .to_string()
         }
@@ -82,74 +207,189 @@ pub fn create_worktree(
     // Ensure parent directory exists for non-sibling locations
     if location != "sibling" {
         if let Some(parent) = Path::new(&worktree_path).parent() {
-            std::fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create worktree parent directory: {}", e))?;
+            std::fs::create_dir_all(parent).map_err(|e| WorktreeError::Io {
+                message: format!("Failed to create worktree parent directory: {}", e),
+            })?;
         }
     }
 
     let output = Command::new("git")
-        .args([
-            "worktree",
-            "add",
-            "-b",
-            branch_name,
-            &worktree_path,
-        ])
+        .args(["worktree", "add"])
+        .args(&checkout.options)
+        .args(sparse_dirs.first().map(|_| "--no-checkout"))
+        .arg(&worktree_path)
+        .args(&checkout.commit_ish)
         .current_dir(repo_root)
         .output()
-        .map_err(|e| format!("Failed to run git worktree add: {}", e))?;
+        .map_err(|e| WorktreeError::Io {
+            message: format!("Failed to run git worktree add: {}", e),
+        })?;
 
-    if output.status.success() {
-        Ok(worktree_path)
-    } else {
+    if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(format!("git worktree add failed: {}", stderr.trim()))
+        return Err(WorktreeError::from_worktree_add(
+            &stderr,
+            checkout.branch.as_deref(),
+            &worktree_path,
+        ));
     }
+
+    if !sparse_dirs.is_empty() {
+        if let Err(e) = sparse::checkout_sparse(&worktree_path, &sparse_dirs) {
+            // Don't leave a half-populated worktree (or its new branch) behind
+            let _ = run_git(
+                repo_root,
+                &["worktree", "remove", "--force", &worktree_path],
+            );
+            if let (WorktreeSource::NewBranch, Some(branch)) = (source, &checkout.branch) {
+                let _ = run_git(repo_root, &["branch", "-D", branch]);
+            }
+            return Err(e);
+        }
+    }
+    // Best-effort: an unlocked worktree still works
+    let _ = scan::lock_worktree(repo_root, &worktree_path, session_id);
+    // Only a branch made here is Clutch's to delete; existing ones belong to the user
+    let created_branch = match source {
+        WorktreeSource::NewBranch => checkout.branch.as_deref(),
+        _ => None,
+    };
+    let _ = scan::mark_clutch_worktree(&worktree_path, session_id, created_branch);
+    Ok(worktree_path)
 }
 
-/// Orchestrate worktree setup for a session. Never errors — returns
-/// the original dir on failure so the session can still proceed.
+/// Orchestrate worktree setup for session `session_id`. Directories outside a git
+/// repo fall back to the original dir. Creation failures are handled per `policy`:
+/// `Fallback` returns the original dir with `error` set, `Retry` and `Abort`
+/// return the error so the caller can refuse to start the session.
+/// With `init_submodules`, submodules and LFS files are checked out before the
+/// repo's setup script runs. Their output is streamed line by line to `on_output`
+/// along with the stage producing it ("submodules", "lfs" or "setup").
 pub fn setup_worktree_for_session(
     working_dir: &str,
     branch_name: &str,
-    location: &str,
-) -> WorktreeSetupResult {
+    session_id: &str,
+    options: &WorktreeOptions,
+    on_output: &mut dyn FnMut(&'static str, &str),
+) -> Result<WorktreeSetupResult, WorktreeSetupError> {
+    let WorktreeOptions {
+        location,
+        source,
+        sparse_dirs,
+        policy,
+        init_submodules,
+    } = *options;
     let fallback = WorktreeSetupResult {
         effective_dir: working_dir.to_string(),
         worktree_path: None,
         git_repo_path: None,
+        branch_name: None,
+        error: None,
+        included_files: Vec::new(),
+        submodules: None,
+        lfs: None,
+        setup: None,
     };
 
     let repo_root = match find_git_root(working_dir) {
         Some(root) => root,
-        None => return fallback,
+        None => return Ok(fallback),
     };
 
-    match create_worktree(&repo_root, branch_name, location) {
-        Ok(wt_path) => WorktreeSetupResult {
-            effective_dir: wt_path.clone(),
-            worktree_path: Some(wt_path),
-            git_repo_path: Some(repo_root),
-        },
-        Err(_) => fallback,
+    let can_rename = matches!(source, WorktreeSource::NewBranch);
+    let mut attempt_branch = branch_name.to_string();
+    let mut attempt = 1;
+    let created = loop {
+        match create_worktree(
+            &repo_root,
+            &attempt_branch,
+            location,
+            source,
+            sparse_dirs,
+            session_id,
+        ) {
+            Err(e)
+                if policy == WorktreeFailurePolicy::Retry
+                    && can_rename
+                    && e.is_name_conflict()
+                    && attempt < MAX_WORKTREE_ATTEMPTS =>
+            {
+                attempt += 1;
+                attempt_branch = format!("{}-{}", branch_name, attempt);
+            }
+            other => break other,
+        }
+    };
+
+    match created {
+        Ok(wt_path) => {
+            let included_files = include::copy_included_files(&repo_root, &wt_path);
+            let (submodules, lfs) = if init_submodules {
+                (
+                    submodules::update_submodules(&wt_path, &mut |line| {
+                        on_output("submodules", line)
+                    }),
+                    submodules::pull_lfs(&wt_path, &mut |line| on_output("lfs", line)),
+                )
+            } else {
+                (None, None)
+            };
+            let setup = scripts::run_setup_script(&repo_root, &wt_path, &mut |line| {
+                on_output("setup", line)
+            });
+            Ok(WorktreeSetupResult {
+                effective_dir: wt_path.clone(),
+                branch_name: get_branch(&wt_path).filter(|b| b != "HEAD"),
+                included_files,
+                submodules,
+                lfs,
+                setup,
+                worktree_path: Some(wt_path),
+                git_repo_path: Some(repo_root),
+                error: None,
+            })
+        }
+        Err(e) if policy == WorktreeFailurePolicy::Fallback => Ok(WorktreeSetupResult {
+            error: Some(e.into()),
+            ..fallback
+        }),
+        Err(e) => Err(e.into()),
     }
 }
 
-/// Remove a worktree and best-effort delete its branch.
+/// Run the repo's teardown script in the worktree, then remove it.
+/// A failing teardown is reported but does not prevent removal.
+pub fn teardown_and_remove_worktree(
+    repo_root: &str,
+    worktree_path: &str,
+    on_output: &mut dyn FnMut(&str),
+) -> WorktreeRemoveResult {
+    let teardown = scripts::run_teardown_script(repo_root, worktree_path, on_output);
+    WorktreeRemoveResult {
+        teardown,
+        ..remove_worktree(repo_root, worktree_path)
+    }
+}
+
+/// Remove a worktree and best-effort delete the branch Clutch created for it. Clutch's
+/// lock is released first, and restored if the removal fails (e.g. uncommitted changes).
 pub fn remove_worktree(repo_root: &str, worktree_path: &str) -> WorktreeRemoveResult {
+    // The marker goes away with the worktree's admin dir
+    let created_branch = scan::created_branch(repo_root, worktree_path);
+    let lock_reason = scan::unlock_clutch_worktree(repo_root, worktree_path);
     let output = Command::new("git")
         .args(["worktree", "remove", worktree_path])
         .current_dir(repo_root)
         .output();
 
-    match output {
+    let result = match output {
         Ok(o) if o.status.success() => {
-            // Best-effort: extract branch name from worktree path and delete it.
-            let _ = try_delete_worktree_branch(repo_root, worktree_path);
+            delete_created_branch(repo_root, created_branch.as_deref());
 
             WorktreeRemoveResult {
                 success: true,
                 error: None,
+                teardown: None,
             }
         }
         Ok(o) => {
@@ -157,13 +397,23 @@ pub fn remove_worktree(repo_root: &str, worktree_path: &str) -> WorktreeRemoveRe
             WorktreeRemoveResult {
                 success: false,
                 error: Some(stderr),
+                teardown: None,
             }
         }
         Err(e) => WorktreeRemoveResult {
             success: false,
             error: Some(format!("Failed to run git worktree remove: {}", e)),
+            teardown: None,
         },
+    };
+
+    if let (false, Some(reason)) = (result.success, &lock_reason) {
+        let _ = run_git(
+            repo_root,
+            &["worktree", "lock", "--reason", reason, worktree_path],
+        );
     }
+    result
 }
 
 /// Check if a worktree directory still exists on disk.
@@ -172,52 +422,53 @@ pub fn validate_worktree_path(path: &str) -> bool {
 }
 
 /// Get the current git branch name for a directory.
-/// Returns the branch name, or None if not in a git repo.
+/// Returns the branch name (`HEAD` when detached), or None if not in a git repo.
 pub fn get_branch(dir: &str) -> Option<String> {
-    let output = Command::new("git")
-        .args(["rev-parse", "--abbrev-ref", "HEAD"])
-        .current_dir(dir)
-        .output()
-        .ok()?;
+    let repo = open_repo(dir)?;
+    let head = repo.head().ok()?;
+    branch_of_head(&head)
+}
 
-    if output.status.success() {
-        let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        if branch.is_empty() {
-            None
-        } else {
-            Some(branch)
-        }
+fn branch_of_head(head: &git2::Reference) -> Option<String> {
+    if head.is_branch() {
+        head.shorthand().map(str::to_string)
     } else {
-        None
+        Some("HEAD".to_string())
     }
 }
 
-/// Best-effort: find and delete the branch associated with a worktree.
-fn try_delete_worktree_branch(repo_root: &str, worktree_path: &str) {
-    let wt = Path::new(worktree_path);
-    let session_id = match wt.file_name().and_then(|n| n.to_str()) {
-        Some(name) => name,
-        None => return,
-    };
+/// Branch, HEAD commit and dirty flag for the repo containing `dir`.
+pub fn get_git_state(dir: &str) -> Option<GitState> {
+    let repo = open_repo(dir)?;
+    let head = repo.head().ok();
 
-    // List branches matching this session_id
-    let output = Command::new("git")
-        .args(["branch", "--list", &format!("*{}", session_id)])
-        .current_dir(repo_root)
-        .output();
+    let mut opts = git2::StatusOptions::new();
+    opts.include_untracked(true)
+        .include_ignored(false)
+        .recurse_untracked_dirs(false);
+    let dirty = repo
+        .statuses(Some(&mut opts))
+        .map(|statuses| !statuses.is_empty())
+        .unwrap_or(false);
 
-    if let Ok(o) = output {
-        if o.status.success() {
-            let branches = String::from_utf8_lossy(&o.stdout);
-            for line in branches.lines() {
-                let branch = line.trim().trim_start_matches("* ");
-                if branch.ends_with(session_id) {
-                    let _ = Command::new("git")
-                        .args(["branch", "-d", branch])
-                        .current_dir(repo_root)
-                        .output();
-                }
-            }
-        }
+    Some(GitState {
+        branch: head.as_ref().and_then(branch_of_head),
+        head: head.and_then(|h| h.target()).map(|oid| oid.to_string()),
+        dirty,
+    })
+}
+
+/// The worktree's own git dir (HEAD, index) and the repo's common dir (refs, packed-refs).
+/// They are the same directory for the main worktree.
+pub fn git_dirs(dir: &str) -> Option<(PathBuf, PathBuf)> {
+    let repo = open_repo(dir)?;
+    Some((repo.path().to_path_buf(), repo.commondir().to_path_buf()))
+}
+
+/// Best-effort: delete the branch Clutch created for a removed worktree (see
+/// `scan::created_branch`). `-d` keeps it if it has unmerged commits.
+fn delete_created_branch(repo_root: &str, branch: Option<&str>) {
+    if let Some(branch) = branch {
+        let _ = run_git(repo_root, &["branch", "-d", branch]);
     }
 }
@@ -0,0 +1,382 @@
+use super::{open_repo, run_git};
+use git2::BranchType;
+use std::collections::HashSet;
+
+/// Used when no template is configured, e.g. `brave-golden-falcon`.
+pub const DEFAULT_BRANCH_TEMPLATE: &str = "{adjective}-{color}-{animal}";
+/// Give up suffixing after this many taken names.
+const MAX_SUFFIX: u32 = 100;
+/// Keep slugs from long session names readable in the sidebar.
+const MAX_SLUG_LEN: usize = 40;
+
+const ADJECTIVES: &[&str] = &[
+    "agile", "bold", "brave", "bright", "calm", "clever", "cosmic", "crisp", "daring", "eager",
+    "fancy", "fearless", "gentle", "glad", "grand", "happy", "humble", "jolly", "keen", "kind",
+    "lively", "lucky", "merry", "mighty", "nimble", "noble", "patient", "proud", "quick", "quiet",
+    "rapid", "sharp", "shiny", "silent", "smooth", "snappy", "steady", "swift", "tidy", "witty",
+];
+
+const COLORS: &[&str] = &[
+    "amber", "aqua", "azure", "beige", "black", "blue", "bronze", "coral", "crimson", "cyan",
+    "gold", "golden", "gray", "green", "indigo", "ivory", "jade", "lime", "magenta", "maroon",
+    "navy", "olive", "orange", "pink", "plum", "purple", "red", "ruby", "salmon", "silver", "teal",
+    "violet", "white", "yellow",
+];
+
+const ANIMALS: &[&str] = &[
+    "badger", "bat", "bear", "beaver", "bison", "cobra", "crane", "crow", "deer", "dolphin",
+    "eagle", "falcon", "ferret", "finch", "fox", "gecko", "heron", "ibis", "jaguar", "koala",
+    "lemur", "lion", "lynx", "marten", "moose", "newt", "otter", "owl", "panda", "panther",
+    "puffin", "quail", "raven", "seal", "shark", "sloth", "swan", "tiger", "walrus", "wolf",
+];
+
+/// Render a branch name from `template` for the repo containing `dir`.
+///
+/// Placeholders: `{user}` (git `user.name`, else the OS user), `{date}` (YYYY-MM-DD),
+/// `{slug}` (from `description`, else a random adjective-animal), `{ticket}`
+/// (`ticket`, or a leading key like `ABC-123` in `description`), and the random words
+/// `{adjective}`, `{color}` and `{animal}`. Empty placeholders drop out along with their
+/// separators. The result is checked with `git check-ref-format` and, if a branch by that
+/// name exists, suffixed with `-2`, `-3`, ….
+pub fn generate_branch_name(
+    dir: &str,
+    template: &str,
+    description: Option<&str>,
+    ticket: Option<&str>,
+) -> Result<String, String> {
+    let template = if template.trim().is_empty() {
+        DEFAULT_BRANCH_TEMPLATE
+    } else {
+        template.trim()
+    };
+
+    let description = description.unwrap_or_default().trim();
+    let (found_ticket, rest) = split_ticket(description);
+    let ticket = ticket
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .or(found_ticket)
+        .unwrap_or_default();
+
+    let name = sanitize(&render(dir, template, rest, ticket)?);
+    if name.is_empty() {
+        return Err(format!(
+            "Branch template '{}' produced an empty name",
+            template
+        ));
+    }
+    if run_git(dir, &["check-ref-format", "--branch", &name]).is_err() {
+        return Err(format!("'{}' is not a valid branch name", name));
+    }
+    unique_name(dir, &name)
+}
+
+fn render(dir: &str, template: &str, description: &str, ticket: &str) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("Unclosed '{{' in branch template '{}'", template))?;
+        let value = match &rest[start + 1..start + end] {
+            "user" => slugify(&user_name(dir)),
+            "date" => chrono::Local::now().format("%Y-%m-%d").to_string(),
+            "slug" => match slugify(description) {
+                slug if slug.is_empty() => format!("{}-{}", pick(ADJECTIVES), pick(ANIMALS)),
+                slug => slug,
+            },
+            "ticket" => ticket.to_string(),
+            "adjective" => pick(ADJECTIVES).to_string(),
+            "color" => pick(COLORS).to_string(),
+            "animal" => pick(ANIMALS).to_string(),
+            other => {
+                return Err(format!(
+                    "Unknown placeholder '{{{}}}' in branch template",
+                    other
+                ))
+            }
+        };
+        out.push_str(&value);
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn pick(words: &[&'static str]) -> &'static str {
+    words[fastrand::usize(..words.len())]
+}
+
+fn user_name(dir: &str) -> String {
+    open_repo(dir)
+        .and_then(|repo| repo.config().ok()?.get_string("user.name").ok())
+        .or_else(|| std::env::var("USER").ok())
+        .or_else(|| std::env::var("USERNAME").ok())
+        .unwrap_or_default()
+}
+
+/// A leading ticket key (`ABC-123`, `#123`) in a session description, and the rest.
+fn split_ticket(description: &str) -> (Option<&str>, &str) {
+    let (first, rest) = description
+        .split_once(char::is_whitespace)
+        .unwrap_or((description, ""));
+    let first = first.trim_end_matches(':');
+    let is_ticket = match first.strip_prefix('#') {
+        Some(number) => !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()),
+        None => first.rsplit_once('-').is_some_and(|(project, number)| {
+            project.starts_with(|c: char| c.is_ascii_alphabetic())
+                && project.chars().all(|c| c.is_ascii_alphanumeric())
+                && !number.is_empty()
+                && number.chars().all(|c| c.is_ascii_digit())
+        }),
+    };
+    if is_ticket {
+        (Some(first.trim_start_matches('#')), rest.trim())
+    } else {
+        (None, description)
+    }
+}
+
+/// Lowercase ASCII words joined by `-`.
+fn slugify(text: &str) -> String {
+    let slug = text
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    match slug.char_indices().nth(MAX_SLUG_LEN) {
+        Some((cut, _)) => slug[..cut].trim_end_matches('-').to_string(),
+        None => slug,
+    }
+}
+
+/// Replace characters git forbids in ref names and tidy the separators
+/// left behind by empty placeholders.
+fn sanitize(name: &str) -> String {
+    let replaced: String = name
+        .chars()
+        .map(|c| match c {
+            '~' | '^' | ':' | '?' | '*' | '[' | '\\' => '-',
+            c if c.is_whitespace() || c.is_control() => '-',
+            c => c,
+        })
+        .collect();
+
+    replaced
+        .split('/')
+        .map(tidy_component)
+        .filter(|component| !component.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// One `/`-separated part of a ref name, tidied until nothing changes: each fix can
+/// expose another (`a...b`, `@@{{`, `x.lock.lock`).
+fn tidy_component(component: &str) -> String {
+    let mut component = component.to_string();
+    loop {
+        let tidied = component
+            .replace("..", ".")
+            .replace("@{", "@")
+            .replace("--", "-");
+        let tidied = tidied.trim_matches(|c| c == '-' || c == '_' || c == '.');
+        let tidied = tidied.strip_suffix(".lock").unwrap_or(tidied);
+        if tidied == component {
+            return component;
+        }
+        component = tidied.to_string();
+    }
+}
+
+/// `name`, or `name-N` for the first N whose branch doesn't exist in the repo (see
+/// `first_free_name`).
+fn unique_name(dir: &str, name: &str) -> Result<String, String> {
+    let Some(repo) = open_repo(dir) else {
+        return Ok(name.to_string());
+    };
+    let mut local = Vec::new();
+    let mut remote = Vec::new();
+    if let Ok(branches) = repo.branches(None) {
+        for (branch, kind) in branches.flatten() {
+            if let Ok(Some(branch_name)) = branch.name() {
+                match kind {
+                    BranchType::Local => local.push(branch_name.to_string()),
+                    BranchType::Remote => remote.push(branch_name.to_string()),
+                }
+            }
+        }
+    }
+    first_free_name(name, &local, &remote)
+}
+
+/// `name`, or `name-N` for the first N that isn't a `local` branch or a `remote` one
+/// (`origin/name`). Names that an existing branch nests under (`a/b` when `a/b/c`
+/// exists) count as taken too; a name nesting under an existing local branch (`a/b`
+/// when `a` exists) can't be fixed by suffixing and is an error.
+fn first_free_name(name: &str, local: &[String], remote: &[String]) -> Result<String, String> {
+    if let Some(parent) = local.iter().find(|b| name.starts_with(&format!("{}/", b))) {
+        return Err(format!(
+            "Branch '{}' exists, so '{}' can't be created",
+            parent, name
+        ));
+    }
+    let existing: HashSet<&str> = local
+        .iter()
+        .map(String::as_str)
+        .chain(remote.iter().filter_map(|b| Some(b.split_once('/')?.1)))
+        .collect();
+
+    let taken = |candidate: &str| {
+        existing
+            .iter()
+            .any(|b| *b == candidate || b.starts_with(&format!("{}/", candidate)))
+    };
+    if !taken(name) {
+        return Ok(name.to_string());
+    }
+    (2..=MAX_SUFFIX)
+        .map(|n| format!("{}-{}", name, n))
+        .find(|candidate| !taken(candidate))
+        .ok_or_else(|| format!("No free branch name found for '{}'", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    fn assert_valid(name: &str) {
+        let dir = std::env::temp_dir().to_string_lossy().to_string();
+        assert!(
+            run_git(&dir, &["check-ref-format", "--branch", name]).is_ok(),
+            "invalid branch name {:?}",
+            name
+        );
+    }
+
+    #[test]
+    fn sanitize_replaces_whitespace_and_forbidden_characters() {
+        assert_eq!(sanitize("Fix  the\tbug "), "Fix-the-bug");
+        assert_eq!(sanitize("a~b^c:d?e*f[g\\h"), "a-b-c-d-e-f-g-h");
+        assert_eq!(sanitize("line\nbreak\u{7f}"), "line-break");
+        assert_eq!(sanitize("café/naïve"), "café/naïve");
+        assert_eq!(sanitize("  "), "");
+    }
+
+    #[test]
+    fn sanitize_removes_sequences_git_forbids() {
+        assert_eq!(sanitize("feature/../x"), "feature/x");
+        assert_eq!(sanitize("a..b"), "a.b");
+        assert_eq!(sanitize("a...b"), "a.b");
+        assert_eq!(sanitize("x/@{y"), "x/@y");
+        assert_eq!(sanitize("@@{{"), "@@");
+        assert_eq!(sanitize("foo.lock"), "foo");
+        assert_eq!(sanitize("foo.lock.lock/bar"), "foo/bar");
+        assert_eq!(sanitize("foo-.lock"), "foo");
+        assert_eq!(sanitize(".hidden/-dash/_under_"), "hidden/dash/under");
+        assert_eq!(sanitize("a//b/"), "a/b");
+        for raw in [
+            "feature/../x",
+            "a...b",
+            "@@{{",
+            "foo.lock.lock/bar",
+            "foo-.lock",
+            " -x- ",
+            "café/naïve",
+            "a~^:?*[\\b",
+        ] {
+            assert_valid(&sanitize(raw));
+        }
+    }
+
+    #[test]
+    fn slugify_keeps_ascii_words() {
+        assert_eq!(slugify("Fix the Login page!"), "fix-the-login-page");
+        assert_eq!(slugify("  --  "), "");
+        assert_eq!(slugify("Ünïcödé"), "n-c-d");
+        let long = slugify(&"word ".repeat(20));
+        assert!(long.len() <= MAX_SLUG_LEN && !long.ends_with('-'));
+    }
+
+    #[test]
+    fn split_ticket_finds_leading_keys() {
+        assert_eq!(
+            split_ticket("ABC-123: Fix login"),
+            (Some("ABC-123"), "Fix login")
+        );
+        assert_eq!(split_ticket("#42 fix crash"), (Some("42"), "fix crash"));
+        assert_eq!(split_ticket("PROJ-7"), (Some("PROJ-7"), ""));
+        assert_eq!(split_ticket("fix-login bug"), (None, "fix-login bug"));
+        assert_eq!(split_ticket("2024-10 report"), (None, "2024-10 report"));
+        assert_eq!(split_ticket("ABC- thing"), (None, "ABC- thing"));
+        assert_eq!(split_ticket("# heading"), (None, "# heading"));
+        assert_eq!(split_ticket(""), (None, ""));
+    }
+
+    #[test]
+    fn empty_placeholders_drop_out_with_their_separators() {
+        let render = |template: &str, description: &str, ticket: &str| {
+            sanitize(&render("/nonexistent", template, description, ticket).unwrap())
+        };
+        assert_eq!(render("{ticket}-{slug}", "Fix login", ""), "fix-login");
+        assert_eq!(
+            render("{ticket}-{slug}", "Fix login", "ABC-1"),
+            "ABC-1-fix-login"
+        );
+        assert_eq!(
+            render("feat/{ticket}/{slug}", "Fix login", ""),
+            "feat/fix-login"
+        );
+        assert_eq!(render("{ticket}", "", ""), "");
+
+        let random = render("{slug}", "", "");
+        let (adjective, animal) = random.split_once('-').unwrap();
+        assert!(ADJECTIVES.contains(&adjective) && ANIMALS.contains(&animal));
+    }
+
+    #[test]
+    fn render_rejects_malformed_templates() {
+        assert!(render("/nonexistent", "{nope}", "", "").is_err());
+        assert!(render("/nonexistent", "fix-{slug", "", "").is_err());
+        assert_eq!(
+            render("/nonexistent", "plain-name", "", "").unwrap(),
+            "plain-name"
+        );
+    }
+
+    #[test]
+    fn first_free_name_suffixes_taken_names() {
+        assert_eq!(first_free_name("fix", &[], &[]).unwrap(), "fix");
+        assert_eq!(
+            first_free_name("fix", &names(&["fix"]), &[]).unwrap(),
+            "fix-2"
+        );
+        assert_eq!(
+            first_free_name("fix", &names(&["fix", "fix-2"]), &names(&["origin/fix-3"])).unwrap(),
+            "fix-4"
+        );
+        assert_eq!(
+            first_free_name("fix", &[], &names(&["upstream/fix"])).unwrap(),
+            "fix-2"
+        );
+        // A branch nested under the name takes it too
+        assert_eq!(
+            first_free_name("me/fix", &names(&["me/fix/part-1"]), &[]).unwrap(),
+            "me/fix-2"
+        );
+    }
+
+    #[test]
+    fn first_free_name_refuses_names_under_a_local_branch() {
+        assert!(first_free_name("me/fix", &names(&["me"]), &[]).is_err());
+        let taken: Vec<String> = std::iter::once("fix".to_string())
+            .chain((2..=MAX_SUFFIX).map(|n| format!("fix-{}", n)))
+            .collect();
+        assert!(first_free_name("fix", &taken, &[]).is_err());
+    }
+}
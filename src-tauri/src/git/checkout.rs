@@ -0,0 +1,117 @@
+use super::run_git;
+use super::scan::parse_worktree_list;
+use super::WorktreeError;
+use git2::Repository;
+use serde::Deserialize;
+
+/// What a new worktree should check out.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WorktreeSource {
+    /// Create a new branch (named by the caller) from the current HEAD.
+    #[default]
+    NewBranch,
+    /// Check out an existing local branch, or track a remote-tracking one like `origin/feat`.
+    ExistingBranch { branch: String },
+    /// Detached HEAD at any commit-ish, e.g. a fetched PR ref `refs/pull/42/head`.
+    Detached { rev: String },
+}
+
+/// Resolved arguments for `git worktree add [options] <path> [commit-ish]`.
+pub(super) struct WorktreeCheckout {
+    pub folder_name: String,
+    /// Branch the worktree ends up on (`None` for detached HEAD).
+    pub branch: Option<String>,
+    pub options: Vec<String>,
+    pub commit_ish: Option<String>,
+}
+
+/// Work out how to check out `source`. `branch_name` is only used for `NewBranch`.
+pub(super) fn resolve_checkout(
+    repo_root: &str,
+    source: &WorktreeSource,
+    branch_name: &str,
+) -> Result<WorktreeCheckout, WorktreeError> {
+    match source {
+        WorktreeSource::NewBranch => Ok(WorktreeCheckout {
+            folder_name: folder_name_for(branch_name),
+            branch: Some(branch_name.to_string()),
+            options: vec!["-b".to_string(), branch_name.to_string()],
+            commit_ish: None,
+        }),
+        WorktreeSource::ExistingBranch { branch } => {
+            if ref_exists(repo_root, &format!("refs/heads/{}", branch)) {
+                return checkout_local(repo_root, branch);
+            }
+            if !ref_exists(repo_root, &format!("refs/remotes/{}", branch)) {
+                return Err(WorktreeError::BranchNotFound {
+                    branch: branch.clone(),
+                });
+            }
+
+            // `origin/feat` -> a local `feat`, reusing one if it already exists. Tracking is
+            // set up by git's `branch.autoSetupMerge` when the remote is configured.
+            let local = branch.split_once('/').map_or(branch.as_str(), |(_, b)| b);
+            if ref_exists(repo_root, &format!("refs/heads/{}", local)) {
+                return checkout_local(repo_root, local);
+            }
+            Ok(WorktreeCheckout {
+                folder_name: folder_name_for(local),
+                branch: Some(local.to_string()),
+                options: vec!["-b".to_string(), local.to_string()],
+                commit_ish: Some(branch.clone()),
+            })
+        }
+        WorktreeSource::Detached { rev } => {
+            let sha = Repository::open(repo_root)
+                .and_then(|repo| Ok(repo.revparse_single(rev)?.peel_to_commit()?.id()))
+                .map_err(|_| WorktreeError::InvalidRevision { rev: rev.clone() })?
+                .to_string();
+            // Check out the resolved commit, not `rev`: it can't be taken for an option
+            // and the worktree stays at the commit its folder is named after
+            Ok(WorktreeCheckout {
+                folder_name: format!("detached-{}", &sha[..sha.len().min(7)]),
+                branch: None,
+                options: vec!["--detach".to_string()],
+                commit_ish: Some(sha),
+            })
+        }
+    }
+}
+
+fn checkout_local(repo_root: &str, branch: &str) -> Result<WorktreeCheckout, WorktreeError> {
+    ensure_not_checked_out(repo_root, branch)?;
+    Ok(WorktreeCheckout {
+        folder_name: folder_name_for(branch),
+        branch: Some(branch.to_string()),
+        options: Vec::new(),
+        commit_ish: Some(branch.to_string()),
+    })
+}
+
+/// Git refuses to check out one branch in two worktrees; say where it already is.
+fn ensure_not_checked_out(repo_root: &str, branch: &str) -> Result<(), WorktreeError> {
+    let porcelain = run_git(repo_root, &["worktree", "list", "--porcelain"])
+        .map_err(|message| WorktreeError::Git { message })?;
+    match parse_worktree_list(&porcelain)
+        .into_iter()
+        .find(|wt| wt.branch.as_deref() == Some(branch))
+    {
+        Some(wt) => Err(WorktreeError::BranchCheckedOut {
+            branch: branch.to_string(),
+            path: wt.path,
+        }),
+        None => Ok(()),
+    }
+}
+
+fn ref_exists(repo_root: &str, full_ref: &str) -> bool {
+    Repository::open(repo_root)
+        .and_then(|repo| repo.find_reference(full_ref).map(|_| ()))
+        .is_ok()
+}
+
+/// Use the branch name as folder name, replacing `/` with `-` for filesystem safety.
+fn folder_name_for(branch_name: &str) -> String {
+    branch_name.replace('/', "-")
+}
@@ -0,0 +1,164 @@
+use super::{open_repo, run_git, run_git_with_env};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Checkpoints live under hidden refs so they don't show up as branches or tags.
+const CHECKPOINT_REFS: &str = "refs/clutch/checkpoints";
+/// Prefix of the scratch index used for snapshots so the user's staging area is never
+/// touched. Each snapshot gets its own, since several can run at once on a worktree.
+const SNAPSHOT_INDEX: &str = "clutch-checkpoint-index";
+/// Times `create_checkpoint` moves on to the next index when a concurrent one took it.
+const MAX_INDEX_ATTEMPTS: u32 = 5;
+
+static SNAPSHOT_COUNTER: AtomicU64 = AtomicU64::new(0);
+/// Checkpoint commits need an identity even when the repo has none configured.
+const IDENTITY: [(&str, &str); 4] = [
+    ("GIT_AUTHOR_NAME", "Clutch"),
+    ("GIT_AUTHOR_EMAIL", "clutch@localhost"),
+    ("GIT_COMMITTER_NAME", "Clutch"),
+    ("GIT_COMMITTER_EMAIL", "clutch@localhost"),
+];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Checkpoint {
+    /// 1-based, in the order prompts were submitted.
+    pub index: u32,
+    pub commit: String,
+    /// HEAD when the checkpoint was taken; restoring resets the branch here.
+    pub head: String,
+    /// Unix timestamp (seconds).
+    pub created_at: i64,
+}
+
+fn checkpoint_ref(session_id: &str, index: u32) -> String {
+    format!("{}/{}/{}", CHECKPOINT_REFS, session_id, index)
+}
+
+/// Write the full worktree state — staged, unstaged and untracked (but not ignored)
+/// files — as a tree object and return its id.
+pub(super) fn snapshot_tree(dir: &str) -> Result<String, String> {
+    let repo = open_repo(dir).ok_or_else(|| format!("{} is not in a git repository", dir))?;
+    let index_path = repo.path().join(format!(
+        "{}-{}-{}",
+        SNAPSHOT_INDEX,
+        std::process::id(),
+        SNAPSHOT_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    // Start from the real index so unchanged files aren't rehashed
+    let real_index = repo.path().join("index");
+    if real_index.exists() {
+        std::fs::copy(&real_index, &index_path)
+            .map_err(|e| format!("Failed to copy index: {}", e))?;
+    }
+
+    let index = index_path.to_string_lossy().to_string();
+    let env = [("GIT_INDEX_FILE", index.as_str())];
+    let result = run_git_with_env(dir, &["add", "-A"], &env)
+        .and_then(|_| run_git_with_env(dir, &["write-tree"], &env));
+    let _ = std::fs::remove_file(&index_path);
+    result
+}
+
+/// Snapshot the worktree containing `dir` as the session's next checkpoint.
+pub fn create_checkpoint(dir: &str, session_id: &str) -> Result<Checkpoint, String> {
+    let repo = open_repo(dir).ok_or_else(|| format!("{} is not in a git repository", dir))?;
+    let head = repo
+        .head()
+        .ok()
+        .and_then(|h| h.target())
+        .ok_or_else(|| "Cannot checkpoint a repository without commits".to_string())?
+        .to_string();
+
+    let tree = snapshot_tree(dir)?;
+    for _ in 0..MAX_INDEX_ATTEMPTS {
+        let index = list_checkpoints(dir, session_id)?
+            .last()
+            .map_or(1, |c| c.index + 1);
+        let message = format!("Clutch checkpoint {} for session {}", index, session_id);
+        let commit = run_git_with_env(
+            dir,
+            &["commit-tree", &tree, "-p", &head, "-m", &message],
+            &IDENTITY,
+        )?;
+        // An empty old value makes `update-ref` fail rather than overwrite a checkpoint
+        // created concurrently under the same index
+        let checkpoint_ref = checkpoint_ref(session_id, index);
+        match run_git(dir, &["update-ref", &checkpoint_ref, &commit, ""]) {
+            Ok(_) => return find_checkpoint(dir, session_id, index),
+            Err(_) if find_checkpoint(dir, session_id, index).is_ok() => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(format!(
+        "Failed to create a checkpoint for session {}: other checkpoints kept taking its index",
+        session_id
+    ))
+}
+
+/// The session's checkpoints, oldest first.
+pub fn list_checkpoints(dir: &str, session_id: &str) -> Result<Vec<Checkpoint>, String> {
+    let repo = open_repo(dir).ok_or_else(|| format!("{} is not in a git repository", dir))?;
+    let prefix = format!("{}/{}/", CHECKPOINT_REFS, session_id);
+    let refs = repo
+        .references_glob(&format!("{}*", prefix))
+        .map_err(|e| format!("Failed to list checkpoints: {}", e))?;
+
+    let mut checkpoints: Vec<Checkpoint> = refs
+        .flatten()
+        .filter_map(|reference| {
+            let index = reference.name()?.strip_prefix(&prefix)?.parse().ok()?;
+            let commit = reference.peel_to_commit().ok()?;
+            Some(Checkpoint {
+                index,
+                commit: commit.id().to_string(),
+                head: commit.parent_id(0).ok()?.to_string(),
+                created_at: commit.time().seconds(),
+            })
+        })
+        .collect();
+    checkpoints.sort_by_key(|c| c.index);
+    Ok(checkpoints)
+}
+
+fn find_checkpoint(dir: &str, session_id: &str, index: u32) -> Result<Checkpoint, String> {
+    list_checkpoints(dir, session_id)?
+        .into_iter()
+        .find(|c| c.index == index)
+        .ok_or_else(|| format!("Checkpoint {} not found for session {}", index, session_id))
+}
+
+/// Roll the worktree back to checkpoint `index`: the branch is reset to the HEAD
+/// it had then and the files are restored as they were (changes come back unstaged).
+/// The current state is checkpointed first, so the restore itself can be undone;
+/// that checkpoint is returned.
+pub fn restore_checkpoint(dir: &str, session_id: &str, index: u32) -> Result<Checkpoint, String> {
+    let target = find_checkpoint(dir, session_id, index)?;
+    let backup = create_checkpoint(dir, session_id)?;
+
+    run_git(dir, &["reset", "-q", "--hard", &target.head])?;
+    run_git(dir, &["clean", "-fdq", "--", ":/"])?;
+    run_git(dir, &["read-tree", "-u", "--reset", &target.commit])?;
+    run_git(dir, &["reset", "-q"])?;
+    Ok(backup)
+}
+
+/// Unified diff from checkpoint `index` to the current worktree, untracked files included.
+pub fn diff_checkpoint(dir: &str, session_id: &str, index: u32) -> Result<String, String> {
+    let target = find_checkpoint(dir, session_id, index)?;
+    let current = snapshot_tree(dir)?;
+    run_git(dir, &["diff", &target.commit, &current])
+}
+
+/// Drop all of a session's checkpoints (best effort).
+pub fn delete_checkpoints(dir: &str, session_id: &str) {
+    let Some(repo) = open_repo(dir) else {
+        return;
+    };
+    let pattern = format!("{}/{}/*", CHECKPOINT_REFS, session_id);
+    let Ok(refs) = repo.references_glob(&pattern) else {
+        return;
+    };
+    for mut reference in refs.flatten() {
+        let _ = reference.delete();
+    }
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Why a worktree could not be created.
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WorktreeError {
+    BranchExists { branch: String },
+    BranchCheckedOut { branch: String, path: String },
+    BranchNotFound { branch: String },
+    InvalidRevision { rev: String },
+    PathExists { path: String },
+    IndexLocked,
+    Io { message: String },
+    Git { message: String },
+}
+
+impl WorktreeError {
+    /// Classify `git worktree add` stderr into a known cause where possible.
+    pub(super) fn from_worktree_add(stderr: &str, branch: Option<&str>, path: &str) -> Self {
+        let stderr = stderr.trim();
+        let branch = branch.unwrap_or_default().to_string();
+
+        if stderr.contains("index.lock") {
+            WorktreeError::IndexLocked
+        } else if stderr.contains("a branch named") && stderr.contains("already exists") {
+            WorktreeError::BranchExists { branch }
+        } else if stderr.contains("already checked out") || stderr.contains("already used by") {
+            // "... is already checked out at '/path'"
+            let path = stderr
+                .split_once(" at '")
+                .and_then(|(_, rest)| rest.split_once('\''))
+                .map(|(p, _)| p.to_string())
+                .unwrap_or_default();
+            WorktreeError::BranchCheckedOut { branch, path }
+        } else if stderr.contains("already exists") {
+            WorktreeError::PathExists {
+                path: path.to_string(),
+            }
+        } else {
+            WorktreeError::Git {
+                message: stderr.to_string(),
+            }
+        }
+    }
+
+    /// Conflicts that go away by picking a different branch (and so folder) name.
+    pub fn is_name_conflict(&self) -> bool {
+        matches!(
+            self,
+            WorktreeError::BranchExists { .. } | WorktreeError::PathExists { .. }
+        )
+    }
+}
+
+impl fmt::Display for WorktreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorktreeError::BranchExists { branch } => {
+                write!(f, "Branch '{}' already exists", branch)
+            }
+            WorktreeError::BranchCheckedOut { branch, path } if path.is_empty() => {
+                write!(
+                    f,
+                    "Branch '{}' is already checked out in another worktree",
+                    branch
+                )
+            }
+            WorktreeError::BranchCheckedOut { branch, path } => {
+                write!(f, "Branch '{}' is already checked out at {}", branch, path)
+            }
+            WorktreeError::BranchNotFound { branch } => {
+                write!(f, "Branch '{}' not found locally or on any remote", branch)
+            }
+            WorktreeError::InvalidRevision { rev } => {
+                write!(f, "'{}' does not resolve to a commit", rev)
+            }
+            WorktreeError::PathExists { path } => write!(f, "{} already exists", path),
+            WorktreeError::IndexLocked => write!(
+                f,
+                "The repository index is locked by another git process (index.lock exists)"
+            ),
+            WorktreeError::Io { message } | WorktreeError::Git { message } => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+/// A `WorktreeError` together with its message, as sent to the frontend.
+#[derive(Debug, Serialize, Clone)]
+pub struct WorktreeSetupError {
+    #[serde(flatten)]
+    pub cause: WorktreeError,
+    pub message: String,
+}
+
+impl From<WorktreeError> for WorktreeSetupError {
+    fn from(cause: WorktreeError) -> Self {
+        Self {
+            message: cause.to_string(),
+            cause,
+        }
+    }
+}
+
+/// What to do when the worktree for a new session can't be created.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorktreeFailurePolicy {
+    /// Start the session in the original directory and report the error.
+    #[default]
+    Fallback,
+    /// Retry name conflicts with a suffixed branch name; abort on anything else.
+    Retry,
+    /// Fail session creation.
+    Abort,
+}
@@ -0,0 +1,76 @@
+use super::{fork_point, get_git_state, open_repo, run_git};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// A `git format-patch` series, one numbered file per commit, in a directory.
+    Patch,
+    /// A single `git bundle` file that can be fetched from or cloned.
+    Bundle,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ExportResult {
+    /// Files written: the patch series, or the bundle.
+    pub files: Vec<String>,
+    pub commits: usize,
+    /// Commit the export is relative to; the recipient needs it to apply the work.
+    pub base: String,
+    /// Uncommitted changes aren't exported; set so the UI can warn about them.
+    pub has_uncommitted_changes: bool,
+}
+
+/// Export the commits the worktree containing `dir` has made since it forked
+/// from the main checkout, written to `output_path` (a directory for patches,
+/// a file for bundles).
+pub fn export_session_changes(
+    dir: &str,
+    format: ExportFormat,
+    output_path: &str,
+) -> Result<ExportResult, String> {
+    let repo = open_repo(dir).ok_or_else(|| format!("{} is not in a git repository", dir))?;
+    let base = fork_point(&repo)
+        .ok_or_else(|| "Repository has no commits yet".to_string())?
+        .to_string();
+    let head = repo
+        .head()
+        .map_err(|e| format!("Failed to read HEAD: {}", e))?;
+    // Bundles record the ref name, so prefer the branch over a bare HEAD
+    let tip = if head.is_branch() {
+        head.name().unwrap_or("HEAD").to_string()
+    } else {
+        "HEAD".to_string()
+    };
+
+    let range = format!("{}..{}", base, tip);
+    let commits: usize = run_git(dir, &["rev-list", "--count", &range])?
+        .parse()
+        .unwrap_or(0);
+    if commits == 0 {
+        return Err("No commits to export since the branch forked".to_string());
+    }
+
+    let files = match format {
+        ExportFormat::Patch => run_git(
+            dir,
+            &["format-patch", "--binary", "-o", output_path, &range],
+        )?
+        .lines()
+        .map(str::to_string)
+        .collect(),
+        ExportFormat::Bundle => {
+            run_git(dir, &["bundle", "create", output_path, &range])?;
+            vec![output_path.to_string()]
+        }
+    };
+
+    let has_uncommitted_changes = get_git_state(dir).is_some_and(|state| state.dirty);
+
+    Ok(ExportResult {
+        files,
+        commits,
+        base,
+        has_uncommitted_changes,
+    })
+}
@@ -0,0 +1,91 @@
+use super::run_git;
+use std::path::Path;
+
+/// Per-repo list of gitignored files to bring into new worktrees, relative to the repo root.
+///
+/// One glob per line (git pathspec glob syntax: `*` stays within a directory, `**/`
+/// crosses them). Lines prefixed with `symlink:` are linked to the main checkout
+/// instead of copied. Blank lines and `#` comments are ignored.
+const INCLUDE_FILE: &str = ".clutch/worktree-include";
+const SYMLINK_PREFIX: &str = "symlink:";
+
+/// Copy or symlink the ignored files listed in `.clutch/worktree-include` from the
+/// main checkout into a freshly created worktree. Returns the relative paths brought over.
+/// Best-effort: files that fail to copy are skipped.
+pub fn copy_included_files(repo_root: &str, worktree_path: &str) -> Vec<String> {
+    let Ok(content) = std::fs::read_to_string(Path::new(repo_root).join(INCLUDE_FILE)) else {
+        return Vec::new();
+    };
+
+    let mut copy_patterns = Vec::new();
+    let mut link_patterns = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.strip_prefix(SYMLINK_PREFIX) {
+            Some(pattern) => link_patterns.push(pattern.trim()),
+            None => copy_patterns.push(line),
+        }
+    }
+
+    let mut included = Vec::new();
+    for (patterns, symlink) in [(link_patterns, true), (copy_patterns, false)] {
+        for rel in ignored_files_matching(repo_root, &patterns) {
+            let src = Path::new(repo_root).join(&rel);
+            let dst = Path::new(worktree_path).join(&rel);
+            if dst.exists() || dst.is_symlink() {
+                continue;
+            }
+            if let Some(parent) = dst.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+            let result = if symlink {
+                link_file(&src, &dst)
+            } else {
+                std::fs::copy(&src, &dst).map(|_| ())
+            };
+            if result.is_ok() {
+                included.push(rel);
+            }
+        }
+    }
+    included
+}
+
+/// Untracked, ignored files in the main checkout matching any of `patterns`.
+fn ignored_files_matching(repo_root: &str, patterns: &[&str]) -> Vec<String> {
+    if patterns.is_empty() {
+        return Vec::new();
+    }
+    let pathspecs: Vec<String> = patterns.iter().map(|p| format!(":(glob){}", p)).collect();
+    let mut args = vec![
+        "ls-files",
+        "-z",
+        "--others",
+        "--ignored",
+        "--exclude-standard",
+        "--",
+    ];
+    args.extend(pathspecs.iter().map(String::as_str));
+
+    match run_git(repo_root, &args) {
+        Ok(out) => out
+            .split('\0')
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+#[cfg(not(windows))]
+fn link_file(src: &Path, dst: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(src, dst)
+}
+
+/// Symlinks need elevated privileges on most Windows setups, so copy instead.
+#[cfg(windows)]
+fn link_file(src: &Path, dst: &Path) -> std::io::Result<()> {
+    std::fs::copy(src, dst).map(|_| ())
+}
@@ -0,0 +1,126 @@
+use git2::{DiffOptions, Oid, Patch};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FileOverlap {
+    pub path: String,
+    /// Whether the edited line ranges intersect. `None` when the sessions forked
+    /// from different commits, so their line numbers aren't comparable.
+    pub hunks_overlap: Option<bool>,
+}
+
+/// Files changed by both sessions since they forked from the main branch.
+#[derive(Debug, Serialize, Clone)]
+pub struct SessionOverlap {
+    pub session_a: String,
+    pub session_b: String,
+    pub files: Vec<FileOverlap>,
+}
+
+struct WorktreeChanges {
+    session_id: String,
+    git_dir: PathBuf,
+    common_dir: PathBuf,
+    base: Oid,
+    /// Path -> edited line ranges `[start, end)` in the base version.
+    files: BTreeMap<String, Vec<(u32, u32)>>,
+}
+
+/// Compare what each session (session_id -> working_dir) has changed — commits and
+/// uncommitted edits, including untracked files — against where it forked from the
+/// main checkout's HEAD, and report files touched by more than one session of the same repo.
+pub fn detect_overlaps(sessions: &HashMap<String, String>) -> Vec<SessionOverlap> {
+    let mut changes: Vec<WorktreeChanges> = sessions
+        .iter()
+        .filter_map(|(id, dir)| changes_since_fork(id, dir))
+        .collect();
+    changes.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+
+    let mut overlaps = Vec::new();
+    for (i, a) in changes.iter().enumerate() {
+        for b in &changes[i + 1..] {
+            // Sessions sharing a checkout see the same changes; that's not a conflict
+            if a.common_dir != b.common_dir || a.git_dir == b.git_dir {
+                continue;
+            }
+            let files: Vec<FileOverlap> = a
+                .files
+                .iter()
+                .filter_map(|(path, hunks_a)| {
+                    let hunks_b = b.files.get(path)?;
+                    Some(FileOverlap {
+                        path: path.clone(),
+                        hunks_overlap: (a.base == b.base)
+                            .then(|| ranges_intersect(hunks_a, hunks_b)),
+                    })
+                })
+                .collect();
+            if !files.is_empty() {
+                overlaps.push(SessionOverlap {
+                    session_a: a.session_id.clone(),
+                    session_b: b.session_id.clone(),
+                    files,
+                });
+            }
+        }
+    }
+    overlaps
+}
+
+fn changes_since_fork(session_id: &str, dir: &str) -> Option<WorktreeChanges> {
+    let repo = super::open_repo(dir)?;
+    let git_dir = repo.path().canonicalize().ok()?;
+    let common_dir = repo.commondir().canonicalize().ok()?;
+
+    let base = super::fork_point(&repo)?;
+    let base_tree = repo.find_commit(base).ok()?.tree().ok()?;
+
+    let mut opts = DiffOptions::new();
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true)
+        .context_lines(0);
+    let diff = repo
+        .diff_tree_to_workdir_with_index(Some(&base_tree), Some(&mut opts))
+        .ok()?;
+
+    let mut files = BTreeMap::new();
+    for (idx, delta) in diff.deltas().enumerate() {
+        let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+            continue;
+        };
+        let mut ranges = Vec::new();
+        if let Ok(Some(patch)) = Patch::from_diff(&diff, idx) {
+            for h in 0..patch.num_hunks() {
+                if let Ok((hunk, _)) = patch.hunk(h) {
+                    // Pure insertions have no old lines; treat them as touching one line
+                    let start = hunk.old_start();
+                    ranges.push((start, start + hunk.old_lines().max(1)));
+                }
+            }
+        }
+        files.insert(path.to_string_lossy().to_string(), ranges);
+    }
+
+    Some(WorktreeChanges {
+        session_id: session_id.to_string(),
+        git_dir,
+        common_dir,
+        base,
+        files,
+    })
+}
+
+/// Adjacent ranges count as overlapping since git's merge conflicts on them too,
+/// as do files without hunks (binary or mode-only changes).
+fn ranges_intersect(a: &[(u32, u32)], b: &[(u32, u32)]) -> bool {
+    if a.is_empty() || b.is_empty() {
+        return true;
+    }
+    a.iter().any(|&(start_a, end_a)| {
+        b.iter()
+            .any(|&(start_b, end_b)| start_a <= end_b && start_b <= end_a)
+    })
+}
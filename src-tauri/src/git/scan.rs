@@ -0,0 +1,445 @@
+use super::{delete_created_branch, find_git_root, remove_worktree, run_git, WorktreeRemoveResult};
+use git2::Repository;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Marker file written into a worktree's admin dir (`.git/worktrees/<name>/`)
+/// so Clutch can recognise its own worktrees even after the store file is lost.
+/// Holds the session ID and, on a second line, the branch Clutch created for the
+/// worktree: the only branch deleted along with it.
+const MARKER_FILE: &str = "clutch-session";
+/// `git worktree lock` reason, followed by the session ID. Shown by `git worktree list`.
+const LOCK_REASON_PREFIX: &str = "in use by Clutch session ";
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ScannedWorktree {
+    pub path: String,
+    pub head: Option<String>,
+    pub branch: Option<String>,
+    pub is_main: bool,
+    /// Bare main repo, without a checkout.
+    pub bare: bool,
+    pub detached: bool,
+    /// Lock reason, if locked (Clutch locks worktrees while their session runs).
+    pub locked: Option<String>,
+    pub prunable: Option<String>,
+    /// Created by Clutch (marker file present, or under `~/.clutch/worktrees/<repo>/`).
+    pub is_clutch: bool,
+    /// Session ID recorded in the marker file or Clutch's lock reason, if any.
+    pub session_id: Option<String>,
+    /// Clutch worktree that no open tab refers to.
+    pub orphaned: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WorktreeScan {
+    pub repo_root: String,
+    pub worktrees: Vec<ScannedWorktree>,
+}
+
+/// List all worktrees of the repo containing `dir` and flag the Clutch-created ones.
+/// `known_paths` are the worktree paths the frontend already has tabs for.
+pub fn scan_worktrees(dir: &str, known_paths: &[String]) -> Result<WorktreeScan, String> {
+    let repo_root = find_git_root(dir).ok_or_else(|| format!("Not a git repository: {}", dir))?;
+    let porcelain = run_git(&repo_root, &["worktree", "list", "--porcelain"])?;
+    let mut worktrees = parse_worktree_list(&porcelain);
+
+    let marker_sessions: HashMap<PathBuf, String> = admin_dirs_by_worktree(&repo_root)
+        .into_iter()
+        .filter_map(|(path, admin)| Some((path, read_marker(&admin)?.session_id)))
+        .collect();
+    let home_root = home_worktrees_dir(&repo_root);
+    flag_clutch_worktrees(
+        &mut worktrees,
+        &marker_sessions,
+        home_root.as_deref(),
+        known_paths,
+    );
+
+    Ok(WorktreeScan {
+        repo_root,
+        worktrees,
+    })
+}
+
+/// Flag the linked worktrees Clutch created: those with a marker (`marker_sessions`,
+/// by worktree path), a Clutch lock, or under `home_root`. Anything else belongs to
+/// the user or another tool and is never offered for removal.
+fn flag_clutch_worktrees(
+    worktrees: &mut [ScannedWorktree],
+    marker_sessions: &HashMap<PathBuf, String>,
+    home_root: Option<&Path>,
+    known_paths: &[String],
+) {
+    for wt in worktrees.iter_mut().filter(|wt| !wt.is_main) {
+        let path = PathBuf::from(&wt.path);
+        wt.session_id = marker_sessions.get(&path).cloned().or_else(|| {
+            let reason = wt.locked.as_deref()?;
+            Some(lock_session_id(reason)?.to_string())
+        });
+        let under_home = home_root.is_some_and(|root| path.starts_with(root));
+        wt.is_clutch = wt.session_id.is_some() || under_home;
+        wt.orphaned = wt.is_clutch && !known_paths.iter().any(|k| Path::new(k) == path);
+    }
+}
+
+/// The session a `lock_worktree` reason names; `None` for locks by other tools.
+fn lock_session_id(reason: &str) -> Option<&str> {
+    let id = reason.trim().strip_prefix(LOCK_REASON_PREFIX)?;
+    (!id.is_empty()).then_some(id)
+}
+
+/// Parse the output of `git worktree list --porcelain`. The first entry is the main worktree.
+pub(super) fn parse_worktree_list(output: &str) -> Vec<ScannedWorktree> {
+    let mut worktrees: Vec<ScannedWorktree> = Vec::new();
+
+    for line in output.lines() {
+        let (key, value) = match line.split_once(' ') {
+            Some((k, v)) => (k, Some(v.to_string())),
+            None => (line, None),
+        };
+
+        if key == "worktree" {
+            worktrees.push(ScannedWorktree {
+                path: value.unwrap_or_default(),
+                is_main: worktrees.is_empty(),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let Some(wt) = worktrees.last_mut() else {
+            continue;
+        };
+        match key {
+            "HEAD" => wt.head = value,
+            "branch" => wt.branch = value.map(|b| b.trim_start_matches("refs/heads/").to_string()),
+            "bare" => wt.bare = true,
+            "detached" => wt.detached = true,
+            "locked" => wt.locked = Some(value.unwrap_or_default()),
+            "prunable" => wt.prunable = Some(value.unwrap_or_default()),
+            _ => {}
+        }
+    }
+
+    worktrees
+}
+
+/// Absolute path of the repo's common git dir (the main `.git`).
+fn git_common_dir(dir: &str) -> Option<PathBuf> {
+    Repository::open(dir)
+        .ok()
+        .map(|repo| repo.commondir().to_path_buf())
+}
+
+/// Map each linked worktree path to its admin dir by reading `.git/worktrees/*/gitdir`.
+/// Works even when the worktree directory itself has been deleted.
+fn admin_dirs_by_worktree(repo_root: &str) -> HashMap<PathBuf, PathBuf> {
+    let mut map = HashMap::new();
+    let Some(common) = git_common_dir(repo_root) else {
+        return map;
+    };
+    let Ok(entries) = std::fs::read_dir(common.join("worktrees")) else {
+        return map;
+    };
+
+    for entry in entries.flatten() {
+        let admin = entry.path();
+        if let Ok(gitdir) = std::fs::read_to_string(admin.join("gitdir")) {
+            if let Some(worktree) = Path::new(gitdir.trim()).parent() {
+                map.insert(worktree.to_path_buf(), admin);
+            }
+        }
+    }
+    map
+}
+
+/// `~/.clutch/worktrees/{repo_name}` — where "home" location worktrees are created.
+fn home_worktrees_dir(repo_root: &str) -> Option<PathBuf> {
+    let home = crate::config::home_dir().ok()?;
+    let repo_name = Path::new(repo_root).file_name()?;
+    Some(
+        Path::new(&home)
+            .join(crate::config::base_dir_name())
+            .join("worktrees")
+            .join(repo_name),
+    )
+}
+
+struct Marker {
+    session_id: String,
+    created_branch: Option<String>,
+}
+
+fn read_marker(admin: &Path) -> Option<Marker> {
+    parse_marker(&std::fs::read_to_string(admin.join(MARKER_FILE)).ok()?)
+}
+
+fn parse_marker(content: &str) -> Option<Marker> {
+    let mut lines = content.lines().map(str::trim);
+    let session_id = lines.next().filter(|id| !id.is_empty())?.to_string();
+    let created_branch = lines.next().filter(|b| !b.is_empty()).map(str::to_string);
+    Some(Marker {
+        session_id,
+        created_branch,
+    })
+}
+
+/// Record `session_id` in the worktree's admin dir so later scans can identify it,
+/// along with `created_branch` if Clutch created the worktree's branch. Re-marking
+/// (e.g. adopting a leftover worktree) keeps the branch recorded before.
+pub fn mark_clutch_worktree(
+    worktree_path: &str,
+    session_id: &str,
+    created_branch: Option<&str>,
+) -> Result<(), String> {
+    // For a linked worktree, the repo path is its admin dir
+    let repo = Repository::open(worktree_path)
+        .map_err(|e| format!("Failed to open worktree {}: {}", worktree_path, e))?;
+    let created_branch = created_branch
+        .map(str::to_string)
+        .or_else(|| read_marker(repo.path())?.created_branch);
+    let content = match created_branch {
+        Some(branch) => format!("{}\n{}\n", session_id, branch),
+        None => format!("{}\n", session_id),
+    };
+    std::fs::write(repo.path().join(MARKER_FILE), content)
+        .map_err(|e| format!("Failed to write worktree marker: {}", e))
+}
+
+/// The branch Clutch created for the worktree, as recorded by `mark_clutch_worktree`.
+/// `None` for worktrees of existing branches or commits, which keep their branch.
+pub(super) fn created_branch(repo_root: &str, worktree_path: &str) -> Option<String> {
+    read_marker(&admin_dir(repo_root, worktree_path)?)?.created_branch
+}
+
+/// Remove the given worktrees in bulk. For worktrees whose directory is already gone
+/// only their admin dir is deleted, leaving other prunable worktrees alone (unlike a
+/// repo-wide `git worktree prune`). Worktrees Clutch didn't create are refused.
+pub fn prune_worktrees(repo_root: &str, worktree_paths: &[String]) -> Vec<WorktreeRemoveResult> {
+    let clutch_paths: Vec<String> = scan_worktrees(repo_root, &[])
+        .map(|scan| scan.worktrees)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|wt| wt.is_clutch)
+        .map(|wt| wt.path)
+        .collect();
+    worktree_paths
+        .iter()
+        .map(|path| {
+            if !clutch_paths.iter().any(|p| Path::new(p) == Path::new(path)) {
+                return WorktreeRemoveResult {
+                    success: false,
+                    error: Some(format!("Not a worktree created by Clutch: {}", path)),
+                    teardown: None,
+                };
+            }
+            if Path::new(path).is_dir() {
+                return remove_worktree(repo_root, path);
+            }
+            let branch = created_branch(repo_root, path);
+            let removed = remove_admin_dir(repo_root, path);
+            if removed.is_ok() {
+                delete_created_branch(repo_root, branch.as_deref());
+            }
+            WorktreeRemoveResult {
+                success: removed.is_ok(),
+                error: removed.err(),
+                teardown: None,
+            }
+        })
+        .collect()
+}
+
+/// Forget a worktree whose directory is gone, as `git worktree prune` would. Like
+/// prune, a lock held by another tool keeps it.
+fn remove_admin_dir(repo_root: &str, worktree_path: &str) -> Result<(), String> {
+    let admin = admin_dir(repo_root, worktree_path)
+        .ok_or_else(|| format!("Not a worktree of this repo: {}", worktree_path))?;
+    if let Some(reason) = lock_reason(repo_root, worktree_path) {
+        if lock_session_id(&reason).is_none() {
+            return Err(format!("Worktree is locked: {}", reason.trim()));
+        }
+    }
+    std::fs::remove_dir_all(&admin)
+        .map_err(|e| format!("Failed to remove {}: {}", admin.display(), e))
+}
+
+/// The worktree's admin dir (`.git/worktrees/<name>/`), even if its directory is gone.
+fn admin_dir(repo_root: &str, worktree_path: &str) -> Option<PathBuf> {
+    match Repository::open(worktree_path) {
+        Ok(repo) if repo.is_worktree() => Some(repo.path().to_path_buf()),
+        _ => admin_dirs_by_worktree(repo_root).remove(Path::new(worktree_path)),
+    }
+}
+
+fn lock_reason(repo_root: &str, worktree_path: &str) -> Option<String> {
+    let admin = admin_dir(repo_root, worktree_path)?;
+    std::fs::read_to_string(admin.join("locked")).ok()
+}
+
+/// `git worktree lock` the worktree for `session_id`, replacing an earlier Clutch lock.
+/// A lock held by another tool is left alone.
+pub fn lock_worktree(repo_root: &str, worktree_path: &str, session_id: &str) -> Result<(), String> {
+    if let Some(reason) = lock_reason(repo_root, worktree_path) {
+        if !reason.starts_with(LOCK_REASON_PREFIX) {
+            return Ok(());
+        }
+        run_git(repo_root, &["worktree", "unlock", worktree_path])?;
+    }
+    let reason = format!("{}{}", LOCK_REASON_PREFIX, session_id);
+    run_git(
+        repo_root,
+        &["worktree", "lock", "--reason", &reason, worktree_path],
+    )
+    .map(|_| ())
+}
+
+/// Release a lock taken by `lock_worktree`, returning its reason so a failed removal
+/// can restore it. Locks held by other tools are kept.
+pub(super) fn unlock_clutch_worktree(repo_root: &str, worktree_path: &str) -> Option<String> {
+    let reason = lock_reason(repo_root, worktree_path)
+        .filter(|reason| reason.starts_with(LOCK_REASON_PREFIX))?;
+    run_git(repo_root, &["worktree", "unlock", worktree_path]).ok()?;
+    Some(reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PORCELAIN: &str = "\
+worktree /src/app
+HEAD 1111111111111111111111111111111111111111
+branch refs/heads/main
+
+worktree /home/me/.clutch/worktrees/app/fix-login
+HEAD 2222222222222222222222222222222222222222
+branch refs/heads/me/fix-login
+locked in use by Clutch session tab-1
+
+worktree /src/app-gone
+HEAD 3333333333333333333333333333333333333333
+branch refs/heads/feature
+prunable gitdir file points to non-existent location
+
+worktree /src/review
+HEAD 4444444444444444444444444444444444444444
+detached
+locked reviewing a PR
+
+worktree /src/foreign-gone
+HEAD 5555555555555555555555555555555555555555
+detached
+prunable gitdir file points to non-existent location
+
+worktree /src/locked-no-reason
+HEAD 6666666666666666666666666666666666666666
+branch refs/heads/wip
+locked
+";
+
+    fn scanned() -> Vec<ScannedWorktree> {
+        let mut worktrees = parse_worktree_list(PORCELAIN);
+        let markers = HashMap::from([(PathBuf::from("/src/app-gone"), "tab-2".to_string())]);
+        flag_clutch_worktrees(
+            &mut worktrees,
+            &markers,
+            Some(Path::new("/home/me/.clutch/worktrees/app")),
+            &["/src/app-gone".to_string()],
+        );
+        worktrees
+    }
+
+    fn find<'a>(worktrees: &'a [ScannedWorktree], path: &str) -> &'a ScannedWorktree {
+        worktrees.iter().find(|wt| wt.path == path).unwrap()
+    }
+
+    #[test]
+    fn parses_porcelain_entries() {
+        let worktrees = parse_worktree_list(PORCELAIN);
+        assert_eq!(worktrees.len(), 6);
+        assert!(worktrees[0].is_main && !worktrees[1].is_main);
+        assert_eq!(worktrees[0].branch.as_deref(), Some("main"));
+
+        let home = find(&worktrees, "/home/me/.clutch/worktrees/app/fix-login");
+        assert_eq!(home.branch.as_deref(), Some("me/fix-login"));
+        assert_eq!(
+            home.locked.as_deref(),
+            Some("in use by Clutch session tab-1")
+        );
+
+        let gone = find(&worktrees, "/src/app-gone");
+        assert!(gone.prunable.is_some() && gone.locked.is_none());
+
+        let review = find(&worktrees, "/src/review");
+        assert!(review.detached && review.branch.is_none());
+        assert_eq!(
+            review.head.as_deref(),
+            Some("4444444444444444444444444444444444444444")
+        );
+
+        let no_reason = find(&worktrees, "/src/locked-no-reason");
+        assert_eq!(no_reason.locked.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn parses_a_bare_main_repo() {
+        let worktrees = parse_worktree_list(
+            "worktree /src/app.git\nbare\n\n\
+             worktree /src/app-main\nHEAD 1111\nbranch refs/heads/main\n",
+        );
+        assert_eq!(worktrees.len(), 2);
+        assert!(worktrees[0].is_main && worktrees[0].bare && worktrees[0].head.is_none());
+        assert!(!worktrees[1].is_main && !worktrees[1].bare);
+    }
+
+    #[test]
+    fn flags_only_clutch_worktrees() {
+        let worktrees = scanned();
+        assert!(!find(&worktrees, "/src/app").is_clutch);
+
+        let home = find(&worktrees, "/home/me/.clutch/worktrees/app/fix-login");
+        assert!(home.is_clutch && home.orphaned);
+        assert_eq!(home.session_id.as_deref(), Some("tab-1"));
+
+        // Marked, but an open tab still refers to it
+        let gone = find(&worktrees, "/src/app-gone");
+        assert!(gone.is_clutch && !gone.orphaned);
+        assert_eq!(gone.session_id.as_deref(), Some("tab-2"));
+    }
+
+    #[test]
+    fn foreign_worktrees_are_never_offered_for_pruning() {
+        let worktrees = scanned();
+        for path in ["/src/review", "/src/foreign-gone", "/src/locked-no-reason"] {
+            let wt = find(&worktrees, path);
+            assert!(!wt.is_clutch && !wt.orphaned, "{}", path);
+            assert!(wt.session_id.is_none(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn reads_session_from_lock_reason() {
+        assert_eq!(
+            lock_session_id("in use by Clutch session tab-1\n"),
+            Some("tab-1")
+        );
+        assert_eq!(lock_session_id("in use by Clutch session "), None);
+        assert_eq!(lock_session_id("reviewing a PR"), None);
+        assert_eq!(lock_session_id(""), None);
+    }
+
+    #[test]
+    fn parses_marker() {
+        let marker = parse_marker("tab-1\nme/fix-login\n").unwrap();
+        assert_eq!(marker.session_id, "tab-1");
+        assert_eq!(marker.created_branch.as_deref(), Some("me/fix-login"));
+
+        let marker = parse_marker("tab-1\n").unwrap();
+        assert!(marker.created_branch.is_none());
+        assert!(parse_marker("").is_none());
+        assert!(parse_marker("\nme/fix-login\n").is_none());
+    }
+}
@@ -0,0 +1,154 @@
+use serde::Serialize;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+
+/// Per-repo shell script run in each new worktree after creation (e.g. `npm install`).
+const SETUP_SCRIPT: &str = ".clutch/worktree-setup";
+/// Per-repo shell script run in a worktree right before it is removed.
+const TEARDOWN_SCRIPT: &str = ".clutch/worktree-teardown";
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ScriptResult {
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+/// Run the repo's setup script in `worktree_path`, if one exists.
+/// Each line of output is passed to `on_output` as it is produced.
+pub fn run_setup_script(
+    repo_root: &str,
+    worktree_path: &str,
+    on_output: &mut dyn FnMut(&str),
+) -> Option<ScriptResult> {
+    run_script(SETUP_SCRIPT, repo_root, worktree_path, on_output)
+}
+
+/// Run the repo's teardown script in `worktree_path`, if one exists.
+pub fn run_teardown_script(
+    repo_root: &str,
+    worktree_path: &str,
+    on_output: &mut dyn FnMut(&str),
+) -> Option<ScriptResult> {
+    run_script(TEARDOWN_SCRIPT, repo_root, worktree_path, on_output)
+}
+
+/// Scripts are read from the main checkout so they work even when untracked. A script
+/// the user hasn't trusted (see trust.rs) is skipped, since a cloned repo could ship one.
+fn run_script(
+    script: &str,
+    repo_root: &str,
+    worktree_path: &str,
+    on_output: &mut dyn FnMut(&str),
+) -> Option<ScriptResult> {
+    let content = crate::trust::read_trusted(&Path::new(repo_root).join(script))?;
+    if content.trim().is_empty() || !Path::new(worktree_path).is_dir() {
+        return None;
+    }
+
+    let mut command = shell_command(&content);
+    command
+        .current_dir(worktree_path)
+        .env("CLUTCH_ROOT_PATH", repo_root)
+        .env("CLUTCH_WORKTREE_PATH", worktree_path);
+    Some(run_streaming(command, script, on_output))
+}
+
+/// Run `command`, passing each line of its output to `on_output` as it is produced.
+/// `name` identifies the command in error messages.
+pub(super) fn run_streaming(
+    mut command: Command,
+    name: &str,
+    on_output: &mut dyn FnMut(&str),
+) -> ScriptResult {
+    let mut child = match command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            return ScriptResult {
+                success: false,
+                exit_code: None,
+                error: Some(format!("Failed to run {}: {}", name, e)),
+            }
+        }
+    };
+
+    // Interleave stdout and stderr lines as they arrive
+    let (tx, rx) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        forward_lines(stdout, tx.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forward_lines(stderr, tx.clone());
+    }
+    drop(tx);
+    for line in rx {
+        on_output(&line);
+    }
+
+    match child.wait() {
+        Ok(status) => ScriptResult {
+            success: status.success(),
+            exit_code: status.code(),
+            error: (!status.success()).then(|| format!("{} exited with {}", name, status)),
+        },
+        Err(e) => ScriptResult {
+            success: false,
+            exit_code: None,
+            error: Some(format!("Failed to wait for {}: {}", name, e)),
+        },
+    }
+}
+
+/// Lines end at `\n` or `\r`, so progress counters that redraw in place
+/// (git clone, npm) are forwarded as they update.
+fn forward_lines(mut stream: impl Read + Send + 'static, tx: mpsc::Sender<String>) {
+    thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        let mut line = Vec::new();
+        while let Ok(n) = stream.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            for &byte in &buf[..n] {
+                if byte != b'\n' && byte != b'\r' {
+                    line.push(byte);
+                    continue;
+                }
+                if !line.is_empty() {
+                    let text = String::from_utf8_lossy(&line).to_string();
+                    line.clear();
+                    if tx.send(text).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+        if !line.is_empty() {
+            let _ = tx.send(String::from_utf8_lossy(&line).to_string());
+        }
+    });
+}
+
+/// Login shell so PATH additions from profiles apply when launched from Finder.
+#[cfg(not(windows))]
+fn shell_command(script: &str) -> Command {
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+    let mut cmd = Command::new(shell);
+    cmd.args(["-l", "-c", script]);
+    cmd
+}
+
+#[cfg(windows)]
+fn shell_command(script: &str) -> Command {
+    let mut cmd = Command::new("powershell.exe");
+    cmd.args(["-NoProfile", "-Command", script]);
+    cmd
+}
@@ -0,0 +1,112 @@
+use super::{run_git, WorktreeError};
+
+/// Cone directories relative to the repo root, without leading or trailing slashes.
+/// Empty entries (and `/`, meaning the whole repo) are dropped.
+pub(super) fn normalize_cone(dirs: &[String]) -> Vec<String> {
+    dirs.iter()
+        .map(|dir| dir.trim().trim_matches('/').to_string())
+        .filter(|dir| !dir.is_empty())
+        .collect()
+}
+
+/// Restrict a worktree created with `--no-checkout` to `dirs` (plus the files at the
+/// repo root, which cone mode always includes) and check those out.
+pub(super) fn checkout_sparse(worktree_path: &str, dirs: &[String]) -> Result<(), WorktreeError> {
+    let mut args = vec!["sparse-checkout", "set", "--cone", "--"];
+    args.extend(dirs.iter().map(String::as_str));
+    run_git(worktree_path, &args).map_err(|message| WorktreeError::Git { message })?;
+    // The index is still empty after `--no-checkout`; this fills it and the sparse files
+    run_git(worktree_path, &["checkout"]).map_err(|message| WorktreeError::Git { message })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    /// A scratch repo with files in two directories and at its root, removed when dropped.
+    struct TempRepo(PathBuf);
+
+    impl TempRepo {
+        fn new(name: &str) -> Self {
+            let root =
+                std::env::temp_dir().join(format!("clutch-sparse-{}-{}", std::process::id(), name));
+            let _ = std::fs::remove_dir_all(&root);
+            let repo = root.join("repo");
+            for file in [
+                "README.md",
+                "services/api/main.rs",
+                "services/web/app.ts",
+                "libs/x.rs",
+            ] {
+                let path = repo.join(file);
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(path, file).unwrap();
+            }
+            let repo = repo.to_string_lossy().to_string();
+            run_git(&repo, &["init", "-q"]).unwrap();
+            run_git(&repo, &["add", "."]).unwrap();
+            run_git(
+                &repo,
+                &[
+                    "-c",
+                    "user.name=Test",
+                    "-c",
+                    "user.email=test@example.com",
+                    "commit",
+                    "-q",
+                    "-m",
+                    "init",
+                ],
+            )
+            .unwrap();
+            Self(root)
+        }
+
+        fn add_worktree(&self, name: &str) -> String {
+            let path = self.0.join(name).to_string_lossy().to_string();
+            let repo = self.0.join("repo").to_string_lossy().to_string();
+            run_git(
+                &repo,
+                &["worktree", "add", "-q", "--no-checkout", "--detach", &path],
+            )
+            .unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempRepo {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn normalize_cone_drops_slashes_and_whole_repo_entries() {
+        let dirs = ["/services/api/", " libs ", "", "/", "  "].map(String::from);
+        assert_eq!(normalize_cone(&dirs), vec!["services/api", "libs"]);
+    }
+
+    #[test]
+    fn checks_out_only_the_cone_and_root_files() {
+        let repo = TempRepo::new("cone");
+        let worktree = repo.add_worktree("wt");
+        checkout_sparse(&worktree, &normalize_cone(&["services/api/".to_string()])).unwrap();
+
+        let exists = |file: &str| Path::new(&worktree).join(file).exists();
+        assert!(exists("README.md"));
+        assert!(exists("services/api/main.rs"));
+        assert!(!exists("services/web/app.ts"));
+        assert!(!exists("libs/x.rs"));
+        // Files outside the cone are still tracked, not deleted
+        assert_eq!(run_git(&worktree, &["status", "--porcelain"]).unwrap(), "");
+    }
+
+    #[test]
+    fn fails_for_a_directory_that_is_not_a_worktree() {
+        let missing = std::env::temp_dir().join("clutch-sparse-missing");
+        let missing = missing.to_string_lossy().to_string();
+        assert!(checkout_sparse(&missing, &["libs".to_string()]).is_err());
+    }
+}
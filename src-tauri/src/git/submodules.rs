@@ -0,0 +1,46 @@
+use super::scripts::{run_streaming, ScriptResult};
+use std::path::Path;
+use std::process::Command;
+
+/// Populate submodules if the worktree has a `.gitmodules`.
+/// Progress lines are passed to `on_output`.
+pub fn update_submodules(
+    worktree_path: &str,
+    on_output: &mut dyn FnMut(&str),
+) -> Option<ScriptResult> {
+    if !Path::new(worktree_path).join(".gitmodules").is_file() {
+        return None;
+    }
+    let mut command = Command::new("git");
+    command
+        .args(["submodule", "update", "--init", "--recursive", "--progress"])
+        .current_dir(worktree_path);
+    Some(run_streaming(command, "git submodule update", on_output))
+}
+
+/// Replace LFS pointer files with their content if `.gitattributes` routes
+/// anything through the LFS filter. Objects the main checkout already fetched
+/// are shared, so only missing ones are downloaded.
+pub fn pull_lfs(worktree_path: &str, on_output: &mut dyn FnMut(&str)) -> Option<ScriptResult> {
+    let attributes =
+        std::fs::read_to_string(Path::new(worktree_path).join(".gitattributes")).ok()?;
+    if !attributes.contains("filter=lfs") {
+        return None;
+    }
+
+    let installed = Command::new("git")
+        .args(["lfs", "version"])
+        .output()
+        .is_ok_and(|output| output.status.success());
+    if !installed {
+        return Some(ScriptResult {
+            success: false,
+            exit_code: None,
+            error: Some("The repo uses Git LFS but git-lfs is not installed".to_string()),
+        });
+    }
+
+    let mut command = Command::new("git");
+    command.args(["lfs", "pull"]).current_dir(worktree_path);
+    Some(run_streaming(command, "git lfs pull", on_output))
+}
@@ -0,0 +1,286 @@
+use super::checkpoint::snapshot_tree;
+use super::{open_repo, run_git};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// Written to the target's git dir while a transfer is applied.
+const PATCH_FILE: &str = "clutch-transfer.patch";
+
+/// What to take from the source worktree.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TransferSource {
+    /// Commits (any revision the source worktree can resolve), applied in order.
+    Commits { revs: Vec<String> },
+    /// Staged, unstaged and untracked changes on top of the source's HEAD.
+    Uncommitted,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct TransferResult {
+    pub success: bool,
+    /// Commits applied to the target (for an uncommitted diff, 1 if it applied).
+    pub applied: usize,
+    /// Paths left with conflict markers in the target.
+    pub conflicts: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl TransferResult {
+    fn failed(error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..Default::default()
+        }
+    }
+}
+
+/// Export changes from `source_dir` as a patch and apply it to `target_dir` with
+/// a 3-way merge. Commits are applied with `git am`, so they keep their author and
+/// message; an uncommitted diff is applied to the target's index and worktree.
+/// On conflict the target is left mid-merge with conflict markers for resolution.
+pub fn transfer_changes(
+    source_dir: &str,
+    target_dir: &str,
+    source: &TransferSource,
+) -> TransferResult {
+    let patch = match export_patch(source_dir, source) {
+        Ok(patch) => patch,
+        Err(e) => return TransferResult::failed(e),
+    };
+    if patch.is_empty() {
+        return TransferResult {
+            success: true,
+            ..Default::default()
+        };
+    }
+
+    let Some(target) = open_repo(target_dir) else {
+        return TransferResult::failed(format!("{} is not in a git repository", target_dir));
+    };
+    let patch_path = target.path().join(PATCH_FILE);
+    if let Err(e) = std::fs::write(&patch_path, &patch) {
+        return TransferResult::failed(format!("Failed to write patch: {}", e));
+    }
+    let patch_file = patch_path.to_string_lossy().to_string();
+
+    let result = match source {
+        TransferSource::Commits { .. } => apply_commits(target_dir, &patch_file),
+        TransferSource::Uncommitted => apply_diff(target_dir, &patch_file),
+    };
+    let _ = std::fs::remove_file(&patch_path);
+    result
+}
+
+/// Patch text for `source`: an mbox of commits or a plain binary diff.
+pub(super) fn export_patch(dir: &str, source: &TransferSource) -> Result<Vec<u8>, String> {
+    match source {
+        TransferSource::Commits { revs } => {
+            let commits = resolve_commits(dir, revs)?;
+            let mut mbox = Vec::new();
+            for commit in commits {
+                mbox.extend(git_bytes(
+                    dir,
+                    &["format-patch", "-1", "--stdout", "--binary", &commit],
+                )?);
+            }
+            Ok(mbox)
+        }
+        TransferSource::Uncommitted => {
+            let tree = snapshot_tree(dir)?;
+            git_bytes(dir, &["diff", "--binary", "HEAD", &tree])
+        }
+    }
+}
+
+/// The commit ID each revision names, so only IDs (never user input, which could look
+/// like an option) reach `git format-patch`.
+fn resolve_commits(dir: &str, revs: &[String]) -> Result<Vec<String>, String> {
+    let repo = open_repo(dir).ok_or_else(|| format!("{} is not in a git repository", dir))?;
+    revs.iter()
+        .map(|rev| {
+            repo.revparse_single(rev)
+                .and_then(|object| object.peel_to_commit())
+                .map(|commit| commit.id().to_string())
+                .map_err(|_| format!("Unknown revision '{}'", rev))
+        })
+        .collect()
+}
+
+fn apply_commits(dir: &str, patch_file: &str) -> TransferResult {
+    let before = run_git(dir, &["rev-parse", "HEAD"]).ok();
+    let outcome = git_bytes(dir, &["am", "--3way", patch_file]);
+    let applied = before
+        .and_then(|head| run_git(dir, &["rev-list", "--count", &format!("{}..HEAD", head)]).ok())
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(0);
+
+    match outcome {
+        Ok(_) => TransferResult {
+            success: true,
+            applied,
+            ..Default::default()
+        },
+        Err(error) => {
+            let conflicts = conflicted_paths(dir);
+            // Nothing to resolve (e.g. dirty index): don't leave `git am` in progress
+            if conflicts.is_empty() {
+                let _ = run_git(dir, &["am", "--abort"]);
+            }
+            TransferResult {
+                success: false,
+                applied,
+                conflicts,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+fn apply_diff(dir: &str, patch_file: &str) -> TransferResult {
+    match git_bytes(dir, &["apply", "--3way", patch_file]) {
+        Ok(_) => TransferResult {
+            success: true,
+            applied: 1,
+            ..Default::default()
+        },
+        Err(error) => TransferResult {
+            success: false,
+            applied: 0,
+            conflicts: conflicted_paths(dir),
+            error: Some(error),
+        },
+    }
+}
+
+fn conflicted_paths(dir: &str) -> Vec<String> {
+    run_git(dir, &["diff", "--name-only", "--diff-filter=U"])
+        .map(|out| out.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Like `run_git`, but returns stdout untouched: patches must keep their trailing newline.
+fn git_bytes(dir: &str, args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("Failed to run git {}: {}", args.join(" "), e))?;
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!("git {} failed: {}", args.join(" "), stderr.trim()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    /// A scratch repo with one commit and a linked worktree, removed when dropped.
+    struct TempRepo {
+        root: PathBuf,
+        main: String,
+        linked: String,
+    }
+
+    impl TempRepo {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!(
+                "clutch-transfer-{}-{}",
+                std::process::id(),
+                name
+            ));
+            let _ = std::fs::remove_dir_all(&root);
+            let main = root.join("main");
+            std::fs::create_dir_all(&main).unwrap();
+            let main = main.to_string_lossy().to_string();
+            git(&main, &["init", "-q"]);
+            git(&main, &["config", "user.name", "Test"]);
+            git(&main, &["config", "user.email", "test@example.com"]);
+            write(&main, "shared.txt", "one\n");
+            git(&main, &["add", "."]);
+            git(&main, &["commit", "-q", "-m", "init"]);
+
+            let linked = root.join("linked").to_string_lossy().to_string();
+            git(&main, &["worktree", "add", "-q", "-b", "feature", &linked]);
+            Self { root, main, linked }
+        }
+    }
+
+    impl Drop for TempRepo {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.root);
+        }
+    }
+
+    fn git(dir: &str, args: &[&str]) -> String {
+        run_git(dir, args).unwrap()
+    }
+
+    fn write(dir: &str, name: &str, content: &str) {
+        std::fs::write(Path::new(dir).join(name), content).unwrap();
+    }
+
+    fn read(dir: &str, name: &str) -> String {
+        std::fs::read_to_string(Path::new(dir).join(name)).unwrap_or_default()
+    }
+
+    #[test]
+    fn commits_round_trip_with_their_messages() {
+        let repo = TempRepo::new("commits");
+        write(&repo.linked, "shared.txt", "one\ntwo\n");
+        git(&repo.linked, &["commit", "-q", "-am", "Add two"]);
+        write(&repo.linked, "new.bin", "\0binary\0");
+        git(&repo.linked, &["add", "."]);
+        git(&repo.linked, &["commit", "-q", "-m", "Add a binary file"]);
+
+        let source = TransferSource::Commits {
+            revs: vec!["HEAD~1".to_string(), "feature".to_string()],
+        };
+        let patch = export_patch(&repo.linked, &source).unwrap();
+        assert!(patch.starts_with(b"From "));
+
+        let result = transfer_changes(&repo.linked, &repo.main, &source);
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.applied, 2);
+        assert_eq!(read(&repo.main, "shared.txt"), "one\ntwo\n");
+        assert_eq!(read(&repo.main, "new.bin"), "\0binary\0");
+        assert_eq!(
+            git(&repo.main, &["log", "-2", "--format=%s"]),
+            "Add a binary file\nAdd two"
+        );
+    }
+
+    #[test]
+    fn uncommitted_changes_round_trip() {
+        let repo = TempRepo::new("uncommitted");
+        write(&repo.linked, "shared.txt", "one\nchanged\n");
+        write(&repo.linked, "untracked.txt", "new\n");
+
+        let result = transfer_changes(&repo.linked, &repo.main, &TransferSource::Uncommitted);
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(read(&repo.main, "shared.txt"), "one\nchanged\n");
+        assert_eq!(read(&repo.main, "untracked.txt"), "new\n");
+        // The source keeps its changes
+        assert_eq!(read(&repo.linked, "shared.txt"), "one\nchanged\n");
+    }
+
+    #[test]
+    fn rejects_unknown_and_option_like_revisions() {
+        let repo = TempRepo::new("revs");
+        for rev in ["no-such-branch", "--output=/tmp/clutch-pwned", "-o/tmp"] {
+            let source = TransferSource::Commits {
+                revs: vec![rev.to_string()],
+            };
+            assert_eq!(
+                export_patch(&repo.linked, &source),
+                Err(format!("Unknown revision '{}'", rev))
+            );
+        }
+        assert!(!Path::new("/tmp/clutch-pwned").exists());
+    }
+}
@@ -0,0 +1,228 @@
+use crate::git::{self, GitState};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+
+/// A single git command touches several files (lock, rename, reflog);
+/// wait for the burst to settle before re-reading state.
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// What makes the worker re-read git state.
+enum Trigger {
+    /// Files changed under a watched git dir.
+    Paths(Vec<PathBuf>),
+    /// The session's agent may have edited files, which only show up in the worktree.
+    Session(String),
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct GitStateChangedPayload {
+    pub session_id: String,
+    #[serde(flatten)]
+    pub state: GitState,
+}
+
+struct WatchedSession {
+    working_dir: String,
+    git_dir: PathBuf,
+    common_dir: PathBuf,
+    last: Option<GitState>,
+}
+
+impl WatchedSession {
+    /// HEAD and index live in the worktree's own git dir; refs are shared by all worktrees.
+    fn watch_paths(&self) -> Vec<(PathBuf, RecursiveMode)> {
+        let mut paths = vec![(self.git_dir.clone(), RecursiveMode::NonRecursive)];
+        if self.common_dir != self.git_dir {
+            paths.push((self.common_dir.clone(), RecursiveMode::NonRecursive));
+        }
+        paths.push((self.common_dir.join("refs"), RecursiveMode::Recursive));
+        paths
+    }
+
+    fn is_affected_by(&self, path: &Path) -> bool {
+        path.parent() == Some(self.git_dir.as_path())
+            || path.starts_with(self.common_dir.join("refs"))
+            || path == self.common_dir.join("packed-refs")
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    watcher: Option<RecommendedWatcher>,
+    sessions: HashMap<String, WatchedSession>,
+    /// Watched path -> number of sessions using it (worktrees share the common dir).
+    watched: HashMap<PathBuf, usize>,
+}
+
+impl Inner {
+    fn watch(&mut self, path: PathBuf, mode: RecursiveMode) {
+        let count = self.watched.entry(path.clone()).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            if let Some(watcher) = self.watcher.as_mut() {
+                let _ = watcher.watch(&path, mode);
+            }
+        }
+    }
+
+    fn unwatch(&mut self, path: &Path) {
+        let Some(count) = self.watched.get_mut(path) else {
+            return;
+        };
+        *count -= 1;
+        if *count == 0 {
+            self.watched.remove(path);
+            if let Some(watcher) = self.watcher.as_mut() {
+                let _ = watcher.unwatch(path);
+            }
+        }
+    }
+}
+
+/// Watches the git dirs of running sessions and emits `git-state-changed`
+/// whenever a session's branch, HEAD commit or dirty flag changes.
+///
+/// Edits to the working tree don't touch the git dir, so the dirty flag is also
+/// re-read when a session reports a tool call or a finished turn (see `refresh`).
+pub struct GitWatcher {
+    inner: Arc<Mutex<Inner>>,
+    tx: mpsc::Sender<Trigger>,
+}
+
+impl GitWatcher {
+    pub fn new(app_handle: AppHandle) -> Self {
+        let (tx, rx) = mpsc::channel::<Trigger>();
+        let watcher_tx = tx.clone();
+        let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                let _ = watcher_tx.send(Trigger::Paths(event.paths));
+            }
+        })
+        .ok();
+
+        let inner = Arc::new(Mutex::new(Inner {
+            watcher,
+            ..Default::default()
+        }));
+
+        let worker_inner = inner.clone();
+        thread::spawn(move || {
+            while let Ok(first) = rx.recv() {
+                thread::sleep(DEBOUNCE);
+                let mut paths = Vec::new();
+                let mut refreshed = HashSet::new();
+                for trigger in std::iter::once(first).chain(rx.try_iter()) {
+                    match trigger {
+                        Trigger::Paths(more) => paths.extend(more),
+                        Trigger::Session(id) => {
+                            refreshed.insert(id);
+                        }
+                    }
+                }
+
+                let changed: Vec<GitStateChangedPayload> = {
+                    let Ok(mut inner) = worker_inner.lock() else {
+                        break;
+                    };
+                    inner
+                        .sessions
+                        .iter_mut()
+                        .filter(|(id, s)| {
+                            refreshed.contains(*id) || paths.iter().any(|p| s.is_affected_by(p))
+                        })
+                        .filter_map(|(id, s)| {
+                            let state = git::get_git_state(&s.working_dir)?;
+                            if s.last.as_ref() == Some(&state) {
+                                return None;
+                            }
+                            s.last = Some(state.clone());
+                            Some(GitStateChangedPayload {
+                                session_id: id.clone(),
+                                state,
+                            })
+                        })
+                        .collect()
+                };
+
+                for payload in changed {
+                    let _ = app_handle.emit("git-state-changed", payload);
+                }
+            }
+        });
+
+        Self { inner, tx }
+    }
+
+    /// Re-read the session's git state, e.g. after its agent edited files.
+    pub fn refresh(&self, session_id: &str) {
+        let _ = self.tx.send(Trigger::Session(session_id.to_string()));
+    }
+
+    /// Make the watched set match `sessions` (session_id -> working_dir)
+    /// and return the current state of each session inside a git repo.
+    fn sync(&self, sessions: HashMap<String, String>) -> HashMap<String, GitState> {
+        let Ok(mut inner) = self.inner.lock() else {
+            return HashMap::new();
+        };
+
+        let stale: Vec<String> = inner
+            .sessions
+            .iter()
+            .filter(|(id, s)| sessions.get(*id) != Some(&s.working_dir))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in stale {
+            if let Some(session) = inner.sessions.remove(&id) {
+                for (path, _) in session.watch_paths() {
+                    inner.unwatch(&path);
+                }
+            }
+        }
+
+        for (id, working_dir) in sessions {
+            if inner.sessions.contains_key(&id) {
+                continue;
+            }
+            let Some((git_dir, common_dir)) = git::git_dirs(&working_dir) else {
+                continue;
+            };
+            // FSEvents reports resolved paths (e.g. /private/var for /var)
+            let git_dir = git_dir.canonicalize().unwrap_or(git_dir);
+            let common_dir = common_dir.canonicalize().unwrap_or(common_dir);
+            let session = WatchedSession {
+                last: git::get_git_state(&working_dir),
+                working_dir,
+                git_dir,
+                common_dir,
+            };
+            for (path, mode) in session.watch_paths() {
+                inner.watch(path, mode);
+            }
+            inner.sessions.insert(id, session);
+        }
+
+        inner
+            .sessions
+            .iter()
+            .filter_map(|(id, s)| Some((id.clone(), s.last.clone()?)))
+            .collect()
+    }
+}
+
+/// Called from frontend whenever the set of running sessions changes.
+/// Starts watching the given sessions (session_id -> working_dir), stops watching
+/// any others, and returns their current git state. Later changes arrive as
+/// `git-state-changed` events.
+#[tauri::command]
+pub fn watch_git_state(
+    watcher: State<'_, GitWatcher>,
+    sessions: HashMap<String, String>,
+) -> HashMap<String, GitState> {
+    watcher.sync(sessions)
+}
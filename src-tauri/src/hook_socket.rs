@@ -0,0 +1,287 @@
+use crate::activity::{self, StatusRecord};
+use crate::config;
+use crate::notifications::SessionsDir;
+use crate::permission_prompts::{self, PermissionPrompts};
+use crate::permissions;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// A hook message is one small JSON record; anything bigger isn't from `clutch --hook`.
+const MAX_MESSAGE_LEN: u64 = 64 * 1024;
+
+/// How long a permission request waits for an answer from the app before leaving it to
+/// Claude's own prompt. Claude Code kills hooks after 60 seconds by default.
+const PERMISSION_WAIT: Duration = Duration::from_secs(45);
+
+/// How long a `UserPromptSubmit` hook waits for the prompt's checkpoint before letting
+/// Claude go ahead anyway.
+const CHECKPOINT_WAIT: Duration = Duration::from_secs(20);
+
+/// What `clutch --hook` sends, as one line: the status record plus the Clutch session
+/// it belongs to.
+#[derive(Serialize, Deserialize)]
+struct HookMessage {
+    /// Clutch session ID (`CLUTCH_SESSION_ID`).
+    session_id: String,
+    #[serde(flatten)]
+    record: StatusRecord,
+    /// The hook is waiting for `respond_permission`, and reads the answer back as the
+    /// hook output for Claude (empty to let Claude prompt).
+    #[serde(default)]
+    wait_for_decision: bool,
+}
+
+/// Per-user socket the hooks deliver to: `~/.clutch/hooks.sock`.
+#[cfg(unix)]
+fn socket_path() -> Option<PathBuf> {
+    let home = config::home_dir().ok()?;
+    Some(
+        PathBuf::from(home)
+            .join(config::base_dir_name())
+            .join("hooks.sock"),
+    )
+}
+
+/// Pipe names are machine-wide, so this one includes the user.
+#[cfg(windows)]
+fn pipe_name() -> String {
+    let user = std::env::var("USERNAME").unwrap_or_default();
+    format!(
+        r"\\.\pipe\{}-hooks-{}",
+        config::base_dir_name().trim_start_matches('.'),
+        user
+    )
+}
+
+/// Listen for hook events for the app's lifetime, emitting each as `session-activity`.
+pub fn start(app: AppHandle, sessions_dir: Arc<SessionsDir>) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = serve(app, sessions_dir).await {
+            eprintln!("[clutch:hooks] hook socket unavailable: {}", e);
+        }
+    });
+}
+
+/// Remove the socket file on exit so hooks fall back to status files straight away.
+pub fn stop() {
+    #[cfg(unix)]
+    if let Some(path) = socket_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[cfg(unix)]
+async fn serve(app: AppHandle, sessions_dir: Arc<SessionsDir>) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = socket_path().ok_or_else(|| io::Error::other("Cannot determine home directory"))?;
+    // Left behind if the app crashed
+    let _ = std::fs::remove_file(&path);
+    let listener = tokio::net::UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let app = app.clone();
+                let sessions_dir = sessions_dir.clone();
+                tauri::async_runtime::spawn(receive(stream, app, sessions_dir));
+            }
+            Err(e) => eprintln!("[clutch:hooks] accept failed: {}", e),
+        }
+    }
+}
+
+#[cfg(windows)]
+async fn serve(app: AppHandle, sessions_dir: Arc<SessionsDir>) -> io::Result<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let name = pipe_name();
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(&name)?;
+
+    loop {
+        server.connect().await?;
+        // Open the next instance before handing this one off, so clients never miss it
+        let connected = std::mem::replace(&mut server, ServerOptions::new().create(&name)?);
+        let app = app.clone();
+        let sessions_dir = sessions_dir.clone();
+        tauri::async_runtime::spawn(receive(connected, app, sessions_dir));
+    }
+}
+
+/// Read one message and publish it. A permission request waiting for a decision
+/// keeps the connection until it is answered or released, and a submitted prompt until
+/// its checkpoint is taken.
+async fn receive(
+    mut stream: impl AsyncRead + AsyncWrite + Unpin,
+    app: AppHandle,
+    sessions_dir: Arc<SessionsDir>,
+) {
+    let mut bytes = Vec::new();
+    let mut reader = BufReader::new(&mut stream).take(MAX_MESSAGE_LEN);
+    if reader.read_until(b'\n', &mut bytes).await.is_err() {
+        return;
+    }
+    let Ok(message) = serde_json::from_slice::<HookMessage>(&bytes) else {
+        return;
+    };
+
+    let prompts = app.state::<PermissionPrompts>();
+    let answer = if message.wait_for_decision {
+        prompts.wait(&message.session_id)
+    } else {
+        // Claude moved on, so a prompt still waiting was answered in the terminal
+        if matches!(
+            message.record.event.as_str(),
+            "PostToolUse" | "UserPromptSubmit" | "Stop" | "SessionEnd"
+        ) {
+            prompts.cancel(&message.session_id);
+        }
+        None
+    };
+
+    let activity = sessions_dir.record_activity(&message.session_id, message.record.into());
+    let is_prompt = activity.event == "UserPromptSubmit";
+    activity::emit_activity(&app, message.session_id.clone(), activity);
+
+    if is_prompt {
+        let session_id = message.session_id.clone();
+        let checkpoint = tauri::async_runtime::spawn_blocking(move || {
+            sessions_dir.checkpoint_new_prompt(&session_id)
+        });
+        let _ = tokio::time::timeout(CHECKPOINT_WAIT, checkpoint).await;
+        let _ = stream.shutdown().await;
+        return;
+    }
+
+    let Some(answer) = answer else {
+        return;
+    };
+    let closed = match tokio::time::timeout(PERMISSION_WAIT, answer).await {
+        Ok(Ok(verdict)) => {
+            let output = permissions::hook_output(&verdict).unwrap_or_default();
+            let _ = stream.write_all(output.as_bytes()).await;
+            true
+        }
+        // Released for a newer event, which already updated the session
+        Ok(Err(_)) => false,
+        Err(_) => {
+            prompts.release(&message.session_id);
+            true
+        }
+    };
+    let _ = stream.shutdown().await;
+    if closed {
+        permission_prompts::emit_closed(&app, message.session_id);
+    }
+}
+
+/// Entry point for `clutch --hook <event>`, which the Claude Code hooks run (see
+/// hooks_config.rs). Reads the hook input from stdin and delivers it to the running
+/// app; if the app isn't listening, appends it to the session's status file instead.
+/// A `PermissionRequest` the repo's policy decides is answered on stdout, and a
+/// `UserPromptSubmit` returns once the app has checkpointed the worktree.
+pub fn send_hook_event(event: &str) {
+    let Ok(session_id) = std::env::var("CLUTCH_SESSION_ID") else {
+        return;
+    };
+    let mut input = String::new();
+    let _ = io::stdin().read_to_string(&mut input);
+    let input: Option<serde_json::Value> = serde_json::from_str(&input).ok();
+
+    let mut permission_decision = None;
+    if let (Some(input), "PermissionRequest") = (&input, event) {
+        let verdict = permissions::evaluate_request(input);
+        if let Some(output) = permissions::hook_output(&verdict) {
+            println!("{}", output);
+            permission_decision = Some(verdict.decision);
+        }
+    }
+
+    let message = HookMessage {
+        session_id,
+        wait_for_decision: event == "PermissionRequest" && permission_decision.is_none(),
+        record: StatusRecord {
+            event: event.to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+            payload: input.and_then(|input| serde_json::from_value(input).ok()),
+            permission_decision,
+        },
+    };
+    let Ok(json) = serde_json::to_string(&message) else {
+        return;
+    };
+    let reply_wait = if message.wait_for_decision {
+        Some(PERMISSION_WAIT)
+    } else if event == "UserPromptSubmit" {
+        Some(CHECKPOINT_WAIT)
+    } else {
+        None
+    };
+    match deliver(&json, reply_wait) {
+        Ok(Some(output)) if !output.is_empty() => println!("{}", output),
+        Ok(_) => {}
+        Err(_) => append_to_status_file(&message.session_id, &message.record),
+    }
+}
+
+/// Send one message line; when the app replies (within `reply_wait`), return its
+/// reply, which is empty if it had no answer in that time.
+fn deliver(json: &str, reply_wait: Option<Duration>) -> io::Result<Option<String>> {
+    let mut stream = connect()?;
+    stream.write_all(json.as_bytes())?;
+    stream.write_all(b"\n")?;
+    let Some(reply_wait) = reply_wait else {
+        return Ok(None);
+    };
+
+    // Read on a thread so the wait can time out on every platform
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut answer = String::new();
+        if stream.read_to_string(&mut answer).is_ok() {
+            let _ = tx.send(answer);
+        }
+    });
+    // A little longer than the app waits, in case the app stops responding
+    Ok(rx.recv_timeout(reply_wait + Duration::from_secs(5)).ok())
+}
+
+#[cfg(unix)]
+fn connect() -> io::Result<std::os::unix::net::UnixStream> {
+    let path = socket_path().ok_or_else(|| io::Error::other("Cannot determine home directory"))?;
+    std::os::unix::net::UnixStream::connect(path)
+}
+
+#[cfg(windows)]
+fn connect() -> io::Result<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(pipe_name())
+}
+
+fn append_to_status_file(session_id: &str, record: &StatusRecord) {
+    let Ok(home) = config::home_dir() else {
+        return;
+    };
+    let path = PathBuf::from(home)
+        .join(config::base_dir_name())
+        .join("sessions")
+        .join(session_id)
+        .join("status");
+    let Ok(line) = serde_json::to_string(record) else {
+        return;
+    };
+    // Only sessions the app created have a status file; don't create one for others
+    if let Ok(mut file) = std::fs::OpenOptions::new().append(true).open(&path) {
+        let _ = writeln!(file, "{}", line);
+    }
+}
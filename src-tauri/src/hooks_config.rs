@@ -1,117 +1,486 @@
 use crate::config;
+use serde::Serialize;
 use serde_json::{json, Value};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Events Clutch registers a hook for.
+const HOOK_EVENTS: &[&str] = &[
+    "SessionStart",
+    "SessionEnd",
+    "UserPromptSubmit",
+    "Stop",
+    "SubagentStop",
+    "Notification",
+    "PreToolUse",
+    "PostToolUse",
+    "PermissionRequest",
+    "PreCompact",
+    "TaskCompleted",
+];
+
+/// Written by `uninstall_hooks` so that launching the app doesn't put them back.
+const HOOKS_DISABLED_FILE: &str = "hooks-disabled";
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEntryStatus {
+    /// Exactly the entry Clutch writes.
+    Installed,
+    Missing,
+    /// One Clutch entry, but not as Clutch wrote it: edited, sharing its matcher
+    /// group with other hooks, or pointing at another copy of the app.
+    Modified,
+    /// More than one Clutch entry.
+    Duplicate,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct EventHookStatus {
+    pub event: String,
+    pub status: HookEntryStatus,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct HooksStatus {
+    pub settings_path: String,
+    pub events: Vec<EventHookStatus>,
+    /// Every event's hook is installed as Clutch writes it.
+    pub installed: bool,
+    /// Hooks were uninstalled, so the app no longer installs them on launch.
+    pub disabled: bool,
+}
 
 fn claude_settings_path() -> Option<PathBuf> {
     let home = config::home_dir().ok()?;
     Some(PathBuf::from(home).join(".claude").join("settings.json"))
 }
 
-fn hook_command(event_name: &str) -> String {
-    let base = config::base_dir_name();
-
+/// Hook command that runs this executable as `--hook <event>` in Clutch sessions,
+/// which passes the event to the app over its socket (see hook_socket.rs).
+fn hook_command(exe: &str, event_name: &str) -> String {
     #[cfg(windows)]
     {
-        // PowerShell command for Windows
+        // PowerShell command for Windows; stdin has to be passed on explicitly
         format!(
-            r#"if ($env:CLUTCH_SESSION_ID) {{ $f = "$env:USERPROFILE\{base}\sessions\$env:CLUTCH_SESSION_ID\status"; $t = "$f.tmp.$PID"; @("{event_name}"; if (Test-Path $f) {{ Get-Content $f }}) | Set-Content $t; Move-Item $t $f -Force }}"#
+            r#"if ($env:CLUTCH_SESSION_ID) {{ [Console]::In.ReadToEnd() | & '{}' --hook {} }}"#,
+            exe.replace('\'', "''"),
+            event_name
         )
     }
 
     #[cfg(not(windows))]
     {
         format!(
-            r#"[ -n "$CLUTCH_SESSION_ID" ] && {{ f="$HOME/{base}/sessions/$CLUTCH_SESSION_ID/status"; t="$f.tmp.$$"; {{ echo "{event_name}"; cat "$f" 2>/dev/null; }} > "$t" && mv "$t" "$f"; }}"#
+            r#"[ -n "$CLUTCH_SESSION_ID" ] && '{}' --hook {}"#,
+            exe.replace('\'', r"'\''"),
+            event_name
         )
     }
 }
 
+/// Install the hooks on launch, unless they were uninstalled. Only writes the settings
+/// file when an entry is missing or out of date.
 pub fn ensure_hooks() {
-    eprintln!("[clutch:hooks] ensuring hooks are configured");
-    let Some(settings_path) = claude_settings_path() else {
-        eprintln!("[clutch:hooks] could not determine settings path");
+    if disabled_marker_path().is_some_and(|path| path.exists()) {
+        eprintln!("[clutch:hooks] hooks were uninstalled; leaving settings alone");
         return;
-    };
-    eprintln!("[clutch:hooks] settings path: {:?}", settings_path);
-
-    let mut settings: Value = if settings_path.exists() {
-        match std::fs::read_to_string(&settings_path) {
-            Ok(content) => serde_json::from_str(&content).unwrap_or(json!({})),
-            Err(_) => return,
+    }
+    let result = load_settings().and_then(|(path, mut settings)| {
+        let exe = current_exe()?;
+        if install(&mut settings, &exe) {
+            eprintln!("[clutch:hooks] updating hooks in {:?}", path);
+            save_settings(&path, &settings)?;
         }
-    } else {
-        json!({})
-    };
+        Ok(())
+    });
+    if let Err(e) = result {
+        eprintln!("[clutch:hooks] {}", e);
+    }
+}
+
+/// Which of Clutch's hooks are installed in `~/.claude/settings.json`.
+#[tauri::command]
+pub fn hooks_status() -> Result<HooksStatus, String> {
+    let (path, settings) = load_settings()?;
+    Ok(status(&path, &settings, &current_exe()?))
+}
+
+/// Install or repair Clutch's hooks: stale, edited and duplicate Clutch entries are
+/// replaced with one current entry per event. Other hooks are left alone.
+#[tauri::command]
+pub fn install_hooks() -> Result<HooksStatus, String> {
+    let (path, mut settings) = load_settings()?;
+    let exe = current_exe()?;
+    if let Some(marker) = disabled_marker_path() {
+        let _ = std::fs::remove_file(marker);
+    }
+    if install(&mut settings, &exe) {
+        save_settings(&path, &settings)?;
+    }
+    Ok(status(&path, &settings, &exe))
+}
+
+/// Remove every Clutch hook (including ones for events it no longer uses) and stop
+/// installing them on launch. Other hooks are left alone.
+#[tauri::command]
+pub fn uninstall_hooks() -> Result<HooksStatus, String> {
+    let (path, mut settings) = load_settings()?;
+    let marker = disabled_marker_path().ok_or("Cannot determine home directory")?;
+    if let Some(parent) = marker.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    std::fs::write(&marker, "")
+        .map_err(|e| format!("Failed to write {}: {}", marker.display(), e))?;
+    if uninstall(&mut settings) {
+        save_settings(&path, &settings)?;
+    }
+    Ok(status(&path, &settings, &current_exe()?))
+}
+
+fn disabled_marker_path() -> Option<PathBuf> {
+    let home = config::home_dir().ok()?;
+    Some(
+        PathBuf::from(home)
+            .join(config::base_dir_name())
+            .join(HOOKS_DISABLED_FILE),
+    )
+}
+
+fn current_exe() -> Result<String, String> {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.to_str().map(str::to_string))
+        .ok_or_else(|| "Could not determine executable path".to_string())
+}
 
-    if let Some(parent) = settings_path.parent() {
+/// The settings file and its contents (`{}` if it doesn't exist yet). A file that
+/// isn't a JSON object is an error rather than something to overwrite.
+fn load_settings() -> Result<(PathBuf, Value), String> {
+    let path = claude_settings_path().ok_or("Could not determine Claude settings path")?;
+    if !path.exists() {
+        return Ok((path, json!({})));
+    }
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let settings: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("{} is not valid JSON: {}", path.display(), e))?;
+    if !settings.is_object() {
+        return Err(format!("{} is not a JSON object", path.display()));
+    }
+    Ok((path, settings))
+}
+
+fn save_settings(path: &Path, settings: &Value) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
         let _ = std::fs::create_dir_all(parent);
     }
+    let formatted = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    std::fs::write(path, formatted)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
 
-    let hooks = settings
-        .as_object_mut()
-        .unwrap()
-        .entry("hooks")
-        .or_insert(json!({}));
+fn status(path: &Path, settings: &Value, exe: &str) -> HooksStatus {
+    let events = event_statuses(settings, exe);
+    HooksStatus {
+        settings_path: path.display().to_string(),
+        installed: events
+            .iter()
+            .all(|e| e.status == HookEntryStatus::Installed),
+        events,
+        disabled: disabled_marker_path().is_some_and(|path| path.exists()),
+    }
+}
 
-    let hooks_obj = match hooks.as_object_mut() {
-        Some(obj) => obj,
-        None => return,
-    };
+fn event_statuses(settings: &Value, exe: &str) -> Vec<EventHookStatus> {
+    let hooks = settings.get("hooks");
+    HOOK_EVENTS
+        .iter()
+        .map(|event| EventHookStatus {
+            event: event.to_string(),
+            status: event_status(hooks, event, &hook_command(exe, event)),
+        })
+        .collect()
+}
 
-    let events = [
-        "UserPromptSubmit",
-        "Stop",
-        "Notification",
-        "PreToolUse",
-        "PermissionRequest",
-        "TaskCompleted",
-    ];
-    for event in events {
-        ensure_hook_entry(hooks_obj, event, &hook_command(event));
-    }
-
-    if let Ok(formatted) = serde_json::to_string_pretty(&settings) {
-        let _ = std::fs::write(&settings_path, formatted);
-    }
-}
-
-/// Ensure exactly one hook entry exists for the given event type with the given command.
-/// Removes any stale Clutch entries and adds the current one if missing.
-fn ensure_hook_entry(
-    hooks_obj: &mut serde_json::Map<String, Value>,
-    event_type: &str,
-    command: &str,
-) {
-    let arr = hooks_obj
-        .entry(event_type)
-        .or_insert(json!([]))
-        .as_array_mut();
-
-    let arr = match arr {
-        Some(a) => a,
-        None => return,
-    };
+fn event_status(hooks: Option<&Value>, event: &str, command: &str) -> HookEntryStatus {
+    let groups = hooks
+        .and_then(|hooks| hooks.get(event))
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let clutch_groups: Vec<&Value> = groups
+        .iter()
+        .filter(|group| group_hooks(group).iter().any(is_clutch_hook))
+        .collect();
+    match clutch_groups.as_slice() {
+        [] => HookEntryStatus::Missing,
+        [group] if **group == hook_entry(command) => HookEntryStatus::Installed,
+        [_] => HookEntryStatus::Modified,
+        _ => HookEntryStatus::Duplicate,
+    }
+}
 
-    // Remove any stale Clutch entries that don't match the current command
-    arr.retain(|entry| {
-        let is_clutch = entry
-            .get("hooks")
-            .and_then(|h| h.as_array())
-            .map(|hooks| {
-                hooks.iter().any(|hook| {
-                    hook.get("command")
-                        .and_then(|c| c.as_str())
-                        .map(|c| c.contains("CLUTCH_SESSION_ID") || c.contains("CLUTCH_NOTIFY_DIR"))
-                        .unwrap_or(false)
-                })
-            })
-            .unwrap_or(false);
-        !is_clutch
+/// Bring every event's hook up to date; returns whether anything changed.
+fn install(settings: &mut Value, exe: &str) -> bool {
+    let mut changed = false;
+    for event in HOOK_EVENTS {
+        let command = hook_command(exe, event);
+        if event_status(settings.get("hooks"), event, &command) == HookEntryStatus::Installed {
+            continue;
+        }
+        let Some(hooks) = settings
+            .as_object_mut()
+            .and_then(|s| s.entry("hooks").or_insert(json!({})).as_object_mut())
+        else {
+            return changed;
+        };
+        // Not ours to fix if the user put something other than a list here
+        let Some(groups) = hooks.entry(*event).or_insert(json!([])).as_array_mut() else {
+            continue;
+        };
+        remove_clutch_hooks(groups);
+        groups.push(hook_entry(&command));
+        changed = true;
+    }
+    changed
+}
+
+/// Remove Clutch's hooks from every event; returns whether anything changed. Events
+/// and the `hooks` object are dropped only if that leaves them empty.
+fn uninstall(settings: &mut Value) -> bool {
+    let Some(hooks) = settings.get_mut("hooks").and_then(Value::as_object_mut) else {
+        return false;
+    };
+    let mut changed = false;
+    hooks.retain(|_, groups| {
+        let Some(groups) = groups.as_array_mut() else {
+            return true;
+        };
+        if !remove_clutch_hooks(groups) {
+            return true;
+        }
+        changed = true;
+        !groups.is_empty()
     });
+    if changed && hooks.is_empty() {
+        if let Some(settings) = settings.as_object_mut() {
+            settings.remove("hooks");
+        }
+    }
+    changed
+}
 
-    // Add the current command
-    arr.push(json!({
+/// The matcher group Clutch adds for an event.
+fn hook_entry(command: &str) -> Value {
+    json!({
         "matcher": "",
         "hooks": [{ "type": "command", "command": command }]
-    }));
+    })
+}
+
+fn group_hooks(group: &Value) -> &[Value] {
+    group
+        .get("hooks")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// Current and older (`CLUTCH_NOTIFY_DIR`) Clutch hook commands.
+fn is_clutch_hook(hook: &Value) -> bool {
+    hook.get("command")
+        .and_then(Value::as_str)
+        .is_some_and(|c| c.contains("CLUTCH_SESSION_ID") || c.contains("CLUTCH_NOTIFY_DIR"))
+}
+
+/// Remove Clutch's hooks from an event's matcher groups, keeping any other hooks that
+/// share a group with them. Returns whether any were removed.
+fn remove_clutch_hooks(groups: &mut Vec<Value>) -> bool {
+    let mut removed = false;
+    groups.retain_mut(|group| {
+        let Some(hooks) = group.get_mut("hooks").and_then(Value::as_array_mut) else {
+            return true;
+        };
+        let before = hooks.len();
+        hooks.retain(|hook| !is_clutch_hook(hook));
+        if hooks.len() == before {
+            return true;
+        }
+        removed = true;
+        !hooks.is_empty()
+    });
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXE: &str = "/Applications/Clutch.app/Contents/MacOS/clutch";
+
+    fn foreign_hook(command: &str) -> Value {
+        json!({ "type": "command", "command": command })
+    }
+
+    fn statuses(settings: &Value) -> Vec<HookEntryStatus> {
+        event_statuses(settings, EXE)
+            .into_iter()
+            .map(|e| e.status)
+            .collect()
+    }
+
+    fn all_installed(settings: &Value) -> bool {
+        statuses(settings)
+            .iter()
+            .all(|s| *s == HookEntryStatus::Installed)
+    }
+
+    #[test]
+    fn installs_into_empty_settings() {
+        let mut settings = json!({});
+        assert!(statuses(&settings)
+            .iter()
+            .all(|s| *s == HookEntryStatus::Missing));
+        assert!(install(&mut settings, EXE));
+        assert!(all_installed(&settings));
+        assert_eq!(
+            settings["hooks"]["Stop"],
+            json!([hook_entry(&hook_command(EXE, "Stop"))])
+        );
+    }
+
+    #[test]
+    fn install_keeps_foreign_hooks_and_unrelated_keys() {
+        let mut settings = json!({
+            "model": "opus",
+            "permissions": { "allow": ["Bash(ls)"] },
+            "hooks": {
+                "PreToolUse": [{ "matcher": "Bash", "hooks": [foreign_hook("audit.sh")] }],
+                "CustomEvent": [{ "matcher": "", "hooks": [foreign_hook("other.sh")] }],
+            }
+        });
+        assert!(install(&mut settings, EXE));
+        assert!(all_installed(&settings));
+        assert_eq!(settings["model"], "opus");
+        assert_eq!(settings["permissions"], json!({ "allow": ["Bash(ls)"] }));
+        assert_eq!(
+            settings["hooks"]["CustomEvent"],
+            json!([{ "matcher": "", "hooks": [foreign_hook("other.sh")] }])
+        );
+        let pre_tool_use = settings["hooks"]["PreToolUse"].as_array().unwrap();
+        assert_eq!(pre_tool_use.len(), 2);
+        assert_eq!(
+            pre_tool_use[0],
+            json!({ "matcher": "Bash", "hooks": [foreign_hook("audit.sh")] })
+        );
+    }
+
+    #[test]
+    fn install_is_idempotent() {
+        let mut settings = json!({ "hooks": { "Stop": [{ "hooks": [foreign_hook("x")] }] } });
+        assert!(install(&mut settings, EXE));
+        let installed = settings.clone();
+        assert!(!install(&mut settings, EXE));
+        assert_eq!(settings, installed);
+    }
+
+    #[test]
+    fn detects_modified_and_duplicate_entries() {
+        let command = hook_command(EXE, "Stop");
+        let shared = json!({
+            "matcher": "",
+            "hooks": [foreign_hook("notify.sh"), foreign_hook(&command)]
+        });
+        let edited = json!({
+            "matcher": "",
+            "hooks": [foreign_hook(&format!("{} --verbose", command))]
+        });
+        let other_copy = hook_entry(&hook_command("/opt/old/clutch", "Stop"));
+        let legacy = hook_entry(r#"[ -n "$CLUTCH_NOTIFY_DIR" ] && echo Stop"#);
+
+        let status =
+            |groups: Value| event_status(Some(&json!({ "Stop": groups })), "Stop", &command);
+        assert_eq!(
+            status(json!([hook_entry(&command)])),
+            HookEntryStatus::Installed
+        );
+        assert_eq!(status(json!([shared])), HookEntryStatus::Modified);
+        assert_eq!(status(json!([edited])), HookEntryStatus::Modified);
+        assert_eq!(status(json!([other_copy])), HookEntryStatus::Modified);
+        assert_eq!(status(json!([legacy])), HookEntryStatus::Modified);
+        assert_eq!(
+            status(json!([hook_entry(&command), other_copy])),
+            HookEntryStatus::Duplicate
+        );
+        assert_eq!(status(json!("not a list")), HookEntryStatus::Missing);
+    }
+
+    #[test]
+    fn install_repairs_shared_and_duplicate_entries() {
+        let command = hook_command(EXE, "Stop");
+        let mut settings = json!({
+            "hooks": {
+                "Stop": [
+                    { "matcher": "", "hooks": [foreign_hook("notify.sh"), foreign_hook(&command)] },
+                    hook_entry(&hook_command("/opt/old/clutch", "Stop")),
+                ]
+            }
+        });
+        assert!(install(&mut settings, EXE));
+        assert_eq!(
+            settings["hooks"]["Stop"],
+            json!([
+                { "matcher": "", "hooks": [foreign_hook("notify.sh")] },
+                hook_entry(&command),
+            ])
+        );
+    }
+
+    #[test]
+    fn install_leaves_events_that_are_not_lists() {
+        let mut settings = json!({ "hooks": { "Stop": "custom" } });
+        assert!(install(&mut settings, EXE));
+        assert_eq!(settings["hooks"]["Stop"], "custom");
+    }
+
+    #[test]
+    fn uninstall_prunes_only_what_it_empties() {
+        let mut settings = json!({ "model": "opus" });
+        install(&mut settings, EXE);
+        settings["hooks"]["PreToolUse"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "matcher": "Bash", "hooks": [foreign_hook("audit.sh")] }));
+        settings["hooks"]["Stop"][0]["hooks"]
+            .as_array_mut()
+            .unwrap()
+            .push(foreign_hook("notify.sh"));
+        settings["hooks"]["CustomEvent"] = json!([]);
+
+        assert!(uninstall(&mut settings));
+        assert_eq!(
+            settings,
+            json!({
+                "model": "opus",
+                "hooks": {
+                    "PreToolUse": [{ "matcher": "Bash", "hooks": [foreign_hook("audit.sh")] }],
+                    "Stop": [{ "matcher": "", "hooks": [foreign_hook("notify.sh")] }],
+                    "CustomEvent": [],
+                }
+            })
+        );
+        assert!(!uninstall(&mut settings));
+    }
+
+    #[test]
+    fn uninstall_removes_the_hooks_object_it_empties() {
+        let mut settings = json!({ "model": "opus" });
+        install(&mut settings, EXE);
+        assert!(uninstall(&mut settings));
+        assert_eq!(settings, json!({ "model": "opus" }));
+
+        let mut not_an_object = json!({ "hooks": [] });
+        assert!(!uninstall(&mut not_an_object));
+        assert_eq!(not_an_object, json!({ "hooks": [] }));
+    }
 }
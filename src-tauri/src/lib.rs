@@ -1,22 +1,49 @@
+mod activity;
 mod commands;
 mod config;
 mod git;
+mod git_watcher;
+mod hook_socket;
 mod hooks_config;
 mod notifications;
+mod permission_prompts;
+mod permissions;
 mod pty;
+mod resume;
+mod timeline;
+mod trust;
+mod usage;
 
 use commands::{
-    cleanup_session_worktree, create_session, destroy_session, get_git_branches, restart_session,
-    session_resize, session_write, setup_session_worktree, validate_worktrees, PtyState,
-    WorktreeRegistry,
+    adopt_worktree, cleanup_session_worktree, create_session, destroy_session,
+    detect_worktree_overlaps, diff_checkpoint, export_session_changes, generate_branch_name,
+    list_checkpoints, prune_worktrees, restart_session, restore_checkpoint, scan_worktrees,
+    session_resize, session_write, setup_session_worktree, transfer_changes, validate_worktrees,
+    PtyState, WorktreeRegistry,
 };
-use notifications::{poll_session_activity, SessionsDir};
+use git_watcher::{watch_git_state, GitWatcher};
+use hooks_config::{hooks_status, install_hooks, uninstall_hooks};
+use notifications::{
+    get_claude_sessions, get_daily_usage, get_session_timeline, get_session_usage,
+    poll_session_activity, SessionsDir,
+};
+use permission_prompts::{respond_permission, PermissionPrompts};
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 #[cfg(target_os = "macos")]
 use tauri::ActivationPolicy;
 use tauri::RunEvent;
 use tauri::{Manager, WindowEvent};
+use trust::{repo_files, set_repo_file_trust};
+
+// For benches/git_poll.rs; not part of the app's API
+#[doc(hidden)]
+pub use git::get_branch;
+
+/// Deliver a Claude Code hook event to the running app (`clutch --hook <event>`).
+pub fn run_hook(event: &str) {
+    hook_socket::send_hook_event(event);
+}
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -29,6 +56,7 @@ pub fn run() {
         .plugin(tauri_plugin_updater::Builder::new().build())
         .manage(PtyState(Mutex::new(HashMap::new())))
         .manage(WorktreeRegistry(Mutex::new(HashSet::new())))
+        .manage(PermissionPrompts::default())
         .manage(Arc::new(
             SessionsDir::new().expect("Failed to create sessions directory"),
         ))
@@ -38,16 +66,41 @@ pub fn run() {
             restart_session,
             session_write,
             session_resize,
+            generate_branch_name,
             setup_session_worktree,
             cleanup_session_worktree,
             validate_worktrees,
+            scan_worktrees,
+            adopt_worktree,
+            prune_worktrees,
             poll_session_activity,
-            get_git_branches,
+            get_session_timeline,
+            get_claude_sessions,
+            get_session_usage,
+            get_daily_usage,
+            respond_permission,
+            hooks_status,
+            install_hooks,
+            uninstall_hooks,
+            repo_files,
+            set_repo_file_trust,
+            watch_git_state,
+            detect_worktree_overlaps,
+            list_checkpoints,
+            restore_checkpoint,
+            diff_checkpoint,
+            transfer_changes,
+            export_session_changes,
         ])
         .setup(|app| {
-            // Auto-configure Claude Code hooks (UserPromptSubmit + Stop + Notification)
+            // Install the Claude Code hooks (unless the user uninstalled them)
             hooks_config::ensure_hooks();
 
+            app.manage(GitWatcher::new(app.handle().clone()));
+            let sessions_dir = app.state::<Arc<SessionsDir>>().inner().clone();
+            sessions_dir.watch(app.handle().clone());
+            hook_socket::start(app.handle().clone(), sessions_dir);
+
             // Handle window close event - hide instead of quit
             if let Some(window) = app.get_webview_window("main") {
                 let app_handle = app.handle().clone();
@@ -81,6 +134,7 @@ pub fn run() {
                     let pty_state = _app.state::<PtyState>();
                     let sessions_dir = _app.state::<Arc<SessionsDir>>();
                     commands::cleanup_all(&pty_state, &sessions_dir);
+                    hook_socket::stop();
                 }
                 _ => {}
             }
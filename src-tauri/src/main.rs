@@ -2,5 +2,13 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // Claude Code hooks run `clutch --hook <event>` (see hooks_config.rs)
+    let args: Vec<String> = std::env::args().collect();
+    if let [_, flag, event] = args.as_slice() {
+        if flag == "--hook" {
+            clutch_desktop_lib::run_hook(event);
+            return;
+        }
+    }
     clutch_desktop_lib::run()
 }
@@ -1,11 +1,34 @@
+use crate::activity::{self, SessionActivity, SessionStateMachine};
 use crate::config;
+use crate::git;
+use crate::resume::{self, ClaudeSession};
+use crate::timeline::{self, SessionTimeline};
+use crate::usage::{DailyUsage, SessionUsage, UsageTracker};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::Arc;
-use tauri::State;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use tauri::{AppHandle, State};
 
 pub struct SessionsDir {
     pub path: PathBuf,
+    /// session_id -> working dir to checkpoint on each UserPromptSubmit
+    checkpoints: Mutex<HashMap<String, String>>,
+    /// session_id -> how far its status file has been read, and the session's state
+    cursors: Mutex<HashMap<String, StatusCursor>>,
+    /// Kept alive for as long as status files are watched.
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    /// Token usage from the transcripts the sessions' hooks report.
+    usage: Arc<UsageTracker>,
+    /// Applied events for the writer thread to persist (see `spawn_writer`).
+    persist: mpsc::Sender<(String, SessionActivity)>,
+}
+
+#[derive(Default)]
+struct StatusCursor {
+    offset: u64,
+    latest: Option<SessionActivity>,
+    machine: SessionStateMachine,
 }
 
 impl SessionsDir {
@@ -19,12 +42,33 @@ impl SessionsDir {
         if config::CLEANUP_STALE_SESSIONS_ON_STARTUP {
             if let Ok(entries) = std::fs::read_dir(&path) {
                 for entry in entries.flatten() {
-                    let _ = std::fs::remove_dir_all(entry.path());
+                    clear_session_dir(&entry.path());
+                }
+            }
+        }
+
+        // Restored tabs send no events until prompted again: count their conversations
+        // from the start
+        let usage = Arc::new(UsageTracker::default());
+        if let Ok(entries) = std::fs::read_dir(&path) {
+            for entry in entries.flatten() {
+                let recorded = resume::load(&entry.path()).and_then(|s| s.transcript_path);
+                if let (Some(transcript_path), Some(session_id)) =
+                    (recorded, entry.file_name().to_str())
+                {
+                    usage.track(session_id, &transcript_path);
                 }
             }
         }
 
-        Ok(Self { path })
+        Ok(Self {
+            persist: spawn_writer(path.clone(), Arc::clone(&usage)),
+            path,
+            checkpoints: Mutex::new(HashMap::new()),
+            cursors: Mutex::new(HashMap::new()),
+            watcher: Mutex::new(None),
+            usage,
+        })
     }
 
     pub fn create_session_dir(&self, session_id: &str) {
@@ -32,41 +76,295 @@ impl SessionsDir {
         let _ = std::fs::create_dir_all(&session_dir);
         // Write empty status file
         let _ = std::fs::write(session_dir.join("status"), "");
+        if let Ok(mut cursors) = self.cursors.lock() {
+            cursors.remove(session_id);
+        }
     }
 
     pub fn remove_session_dir(&self, session_id: &str) {
         let session_dir = self.path.join(session_id);
         let _ = std::fs::remove_dir_all(&session_dir);
+        if let Ok(mut cursors) = self.cursors.lock() {
+            cursors.remove(session_id);
+        }
+    }
+
+    /// Checkpoint `working_dir` each time the session submits a prompt.
+    pub fn track_checkpoints(&self, session_id: &str, working_dir: &str) {
+        if git::find_git_root(working_dir).is_none() {
+            return;
+        }
+        if let Ok(mut map) = self.checkpoints.lock() {
+            map.insert(session_id.to_string(), working_dir.to_string());
+        }
+    }
+
+    /// Stop checkpointing the session and delete its checkpoints.
+    pub fn discard_checkpoints(&self, session_id: &str) {
+        let entry = self
+            .checkpoints
+            .lock()
+            .ok()
+            .and_then(|mut map| map.remove(session_id));
+        if let Some(working_dir) = entry {
+            git::delete_checkpoints(&working_dir, session_id);
+        }
+    }
+
+    /// Record an event delivered over the hook socket, returning it with the
+    /// session's new state.
+    pub fn record_activity(&self, session_id: &str, activity: SessionActivity) -> SessionActivity {
+        let Ok(mut cursors) = self.cursors.lock() else {
+            return activity;
+        };
+        let cursor = cursors.entry(session_id.to_string()).or_default();
+        self.apply(session_id, cursor, activity)
+    }
+
+    /// The session's most recent event, after reading any new status file records.
+    pub fn read_activity(&self, session_id: &str) -> Option<SessionActivity> {
+        self.read_new_activity(session_id);
+        self.cursors.lock().ok()?.get(session_id)?.latest.clone()
+    }
+
+    /// Read records appended to the session's status file (where hooks write when the
+    /// socket is unavailable) since the last call, returning the newest if there are any.
+    /// Their hooks have already returned, so prompts among them are checkpointed late.
+    fn read_new_activity(&self, session_id: &str) -> Option<SessionActivity> {
+        let activities: Vec<SessionActivity> = {
+            let mut cursors = self.cursors.lock().ok()?;
+            let cursor = cursors.entry(session_id.to_string()).or_default();
+            let status_path = self.path.join(session_id).join("status");
+            let (records, offset) = activity::read_new_records(&status_path, cursor.offset);
+            cursor.offset = offset;
+            records
+                .into_iter()
+                .map(|record| self.apply(session_id, cursor, record))
+                .collect()
+        };
+
+        for activity in &activities {
+            if activity.event == "UserPromptSubmit" {
+                self.checkpoint_new_prompt(session_id);
+            }
+        }
+        activities.into_iter().last()
+    }
+
+    /// Advance the session's state machine with a new event and have it logged to the
+    /// timeline. Called with `cursors` locked, so events reach the writer in order.
+    fn apply(
+        &self,
+        session_id: &str,
+        cursor: &mut StatusCursor,
+        mut activity: SessionActivity,
+    ) -> SessionActivity {
+        activity.state = cursor.machine.apply(&activity);
+        let _ = self
+            .persist
+            .send((session_id.to_string(), activity.clone()));
+        cursor.latest = Some(activity.clone());
+        activity
+    }
+
+    /// Every event the session has reported, with prompt durations.
+    pub fn timeline(&self, session_id: &str) -> SessionTimeline {
+        // Pick up status file records the watcher hasn't seen yet
+        self.read_new_activity(session_id);
+        timeline::load(&self.path.join(session_id))
+    }
+
+    /// Tokens the session's Claude conversations have used so far.
+    pub fn usage(&self, session_id: &str) -> SessionUsage {
+        self.usage.session_usage(session_id)
+    }
+
+    /// Today's token usage across all sessions.
+    pub fn daily_usage(&self) -> DailyUsage {
+        self.usage.daily_usage()
+    }
+
+    /// The Claude conversation the session last prompted, if any.
+    pub fn claude_session(&self, session_id: &str) -> Option<ClaudeSession> {
+        resume::load(&self.path.join(session_id))
+    }
+
+    /// Watch the sessions dir and emit `session-activity` as soon as a status file
+    /// gets a new record, so fallback writes don't wait for `poll_session_activity`.
+    pub fn watch(self: &Arc<Self>, app: AppHandle) {
+        let (tx, rx) = mpsc::channel::<PathBuf>();
+        let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                if !event.kind.is_access() {
+                    for path in event.paths {
+                        let _ = tx.send(path);
+                    }
+                }
+            }
+        });
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                eprintln!("[clutch:activity] failed to create watcher: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = watcher.watch(&self.path, RecursiveMode::Recursive) {
+            eprintln!("[clutch:activity] failed to watch {:?}: {}", self.path, e);
+            return;
+        }
+        if let Ok(mut slot) = self.watcher.lock() {
+            *slot = Some(watcher);
+        }
+
+        let sessions_dir = Arc::clone(self);
+        std::thread::spawn(move || {
+            for path in rx {
+                // Removed session dirs also produce events
+                if !path.ends_with("status") || !path.is_file() {
+                    continue;
+                }
+                let Some(session_id) = path
+                    .parent()
+                    .and_then(|dir| dir.file_name())
+                    .and_then(|name| name.to_str())
+                else {
+                    continue;
+                };
+                if let Some(activity) = sessions_dir.read_new_activity(session_id) {
+                    activity::emit_activity(&app, session_id.to_string(), activity);
+                }
+            }
+        });
     }
 
+    /// Snapshot the session's worktree for a prompt it just submitted. Blocks until
+    /// done: the prompt's hook waits for it (see hook_socket.rs), so Claude can't edit
+    /// anything before the snapshot and checkpoints follow the order of prompts.
+    pub fn checkpoint_new_prompt(&self, session_id: &str) {
+        let Some(working_dir) = self
+            .checkpoints
+            .lock()
+            .ok()
+            .and_then(|map| map.get(session_id).cloned())
+        else {
+            return;
+        };
+        if let Err(e) = git::create_checkpoint(&working_dir, session_id) {
+            eprintln!("[clutch:checkpoint] {}: {}", session_id, e);
+        }
+    }
+
+    /// Clear every session dir on exit. Tabs persist, so their Claude sessions and
+    /// timelines are kept.
     pub fn remove_all(&self) {
         if let Ok(entries) = std::fs::read_dir(&self.path) {
             for entry in entries.flatten() {
-                let _ = std::fs::remove_dir_all(entry.path());
+                clear_session_dir(&entry.path());
+            }
+        }
+    }
+}
+
+/// Persist applied events on a thread of their own, so hook events don't wait on disk
+/// writes (the timeline may be rewritten when it gets too big) or on each other.
+fn spawn_writer(
+    sessions_path: PathBuf,
+    usage: Arc<UsageTracker>,
+) -> mpsc::Sender<(String, SessionActivity)> {
+    let (tx, rx) = mpsc::channel::<(String, SessionActivity)>();
+    std::thread::spawn(move || {
+        for (session_id, activity) in rx {
+            let session_dir = sessions_path.join(&session_id);
+            timeline::append(&session_dir, &activity);
+            resume::record(&session_dir, &activity);
+            if let Some(transcript_path) = &activity.transcript_path {
+                usage.track(&session_id, transcript_path);
+            }
+        }
+    });
+    tx
+}
+
+/// Session dir files that outlive the app, so a restored tab keeps its conversation
+/// and history.
+const KEPT_FILES: [&str; 2] = [resume::CLAUDE_SESSION_FILE, timeline::TIMELINE_FILE];
+
+/// Remove a session dir left from a previous run, except for `KEPT_FILES`; a dir
+/// without any of them is removed entirely.
+fn clear_session_dir(session_dir: &Path) {
+    if !KEPT_FILES
+        .iter()
+        .any(|name| session_dir.join(name).is_file())
+    {
+        let _ = std::fs::remove_dir_all(session_dir);
+        return;
+    }
+    if let Ok(entries) = std::fs::read_dir(session_dir) {
+        for entry in entries.flatten() {
+            if !KEPT_FILES.iter().any(|name| entry.file_name() == *name) {
+                let path = entry.path();
+                let _ = std::fs::remove_file(&path).or_else(|_| std::fs::remove_dir_all(&path));
             }
         }
     }
 }
 
-/// Called from frontend on an interval. Reads status files for the given session IDs
-/// and returns a map of session_id -> status content (trimmed).
-/// Only includes sessions whose status file exists and is non-empty.
+/// Latest hook event for each of the given session IDs, omitting sessions that haven't
+/// reported one yet. Events are pushed as `session-activity` (from the hook socket or
+/// the status file watcher); this is the fallback the frontend uses to catch up on
+/// ones it missed, e.g. before it started listening.
 #[tauri::command]
 pub fn poll_session_activity(
     sessions_dir: State<'_, Arc<SessionsDir>>,
     session_ids: Vec<String>,
-) -> HashMap<String, String> {
-    let mut result = HashMap::new();
-    for id in session_ids {
-        let status_path = sessions_dir.path.join(&id).join("status");
-        if let Ok(content) = std::fs::read_to_string(&status_path) {
-            if let Some(first_line) = content.lines().next() {
-                let trimmed = first_line.trim().to_string();
-                if !trimmed.is_empty() {
-                    result.insert(id, trimmed);
-                }
-            }
-        }
-    }
-    result
+) -> HashMap<String, SessionActivity> {
+    session_ids
+        .into_iter()
+        .filter_map(|id| {
+            let activity = sessions_dir.read_activity(&id)?;
+            Some((id, activity))
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn get_session_timeline(
+    sessions_dir: State<'_, Arc<SessionsDir>>,
+    session_id: String,
+) -> SessionTimeline {
+    sessions_dir.timeline(&session_id)
+}
+
+/// Recorded Claude conversations for the given session IDs, omitting sessions without
+/// one. Restored tabs use this to offer resuming where they left off.
+#[tauri::command]
+pub fn get_claude_sessions(
+    sessions_dir: State<'_, Arc<SessionsDir>>,
+    session_ids: Vec<String>,
+) -> HashMap<String, ClaudeSession> {
+    session_ids
+        .into_iter()
+        .filter_map(|id| {
+            let session = sessions_dir.claude_session(&id)?;
+            Some((id, session))
+        })
+        .collect()
+}
+
+/// Tokens and estimated cost for the session, read incrementally from the Claude
+/// transcripts its hooks reported and, across restarts, its last conversation's.
+#[tauri::command]
+pub fn get_session_usage(
+    sessions_dir: State<'_, Arc<SessionsDir>>,
+    session_id: String,
+) -> SessionUsage {
+    sessions_dir.usage(&session_id)
+}
+
+/// Today's tokens and estimated cost across the transcripts Clutch knows of (see
+/// `UsageTracker::daily_usage`).
+#[tauri::command]
+pub fn get_daily_usage(sessions_dir: State<'_, Arc<SessionsDir>>) -> DailyUsage {
+    sessions_dir.daily_usage()
 }
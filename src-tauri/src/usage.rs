@@ -0,0 +1,253 @@
+use crate::activity;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Token counts and estimated cost, for a session, a model or a day.
+#[derive(Debug, Serialize, Clone, Copy, Default, PartialEq)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    /// Estimated from list prices (see `price_per_mtok`); 0 for unknown models.
+    pub cost_usd: f64,
+}
+
+impl TokenUsage {
+    fn add(&mut self, other: &TokenUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct SessionUsage {
+    #[serde(flatten)]
+    pub total: TokenUsage,
+    /// Model of the most recent response.
+    pub model: Option<String>,
+    pub by_model: HashMap<String, TokenUsage>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DailyUsage {
+    /// Local date, `YYYY-MM-DD`.
+    pub date: String,
+    #[serde(flatten)]
+    pub total: TokenUsage,
+}
+
+/// An assistant line of a Claude transcript; other line types carry no usage.
+#[derive(Deserialize)]
+struct TranscriptLine {
+    #[serde(rename = "type")]
+    kind: String,
+    /// RFC 3339, e.g. `2025-06-01T12:34:56.789Z`
+    timestamp: Option<String>,
+    #[serde(rename = "requestId")]
+    request_id: Option<String>,
+    message: Option<TranscriptMessage>,
+}
+
+#[derive(Deserialize)]
+struct TranscriptMessage {
+    id: Option<String>,
+    model: Option<String>,
+    usage: Option<MessageUsage>,
+}
+
+#[derive(Deserialize)]
+struct MessageUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: u64,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+}
+
+/// How much of one transcript has been read, and what it added up to.
+#[derive(Default)]
+struct Transcript {
+    offset: u64,
+    /// A response is logged once per content block, each line repeating its usage.
+    seen: HashSet<String>,
+    usage: SessionUsage,
+    /// Local date -> usage
+    by_day: BTreeMap<String, TokenUsage>,
+}
+
+impl Transcript {
+    fn read_new(&mut self, path: &Path) {
+        let len = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        if len < self.offset {
+            // Rewritten from scratch
+            *self = Transcript::default();
+        }
+        let (lines, offset) = activity::read_new_lines(path, self.offset);
+        self.offset = offset;
+        for line in lines {
+            if let Ok(line) = serde_json::from_str::<TranscriptLine>(&line) {
+                self.add(line);
+            }
+        }
+    }
+
+    fn add(&mut self, line: TranscriptLine) {
+        if line.kind != "assistant" {
+            return;
+        }
+        let Some(message) = line.message else {
+            return;
+        };
+        let Some(usage) = message.usage else {
+            return;
+        };
+        let key = format!(
+            "{}:{}",
+            message.id.unwrap_or_default(),
+            line.request_id.unwrap_or_default()
+        );
+        if key != ":" && !self.seen.insert(key) {
+            return;
+        }
+
+        let model = message.model.unwrap_or_default();
+        let tokens = priced(&model, &usage);
+        let date = line
+            .timestamp
+            .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+            .map_or_else(Local::now, |t| t.with_timezone(&Local))
+            .format("%Y-%m-%d")
+            .to_string();
+
+        self.usage.total.add(&tokens);
+        self.usage
+            .by_model
+            .entry(model.clone())
+            .or_default()
+            .add(&tokens);
+        self.usage.model = Some(model);
+        self.by_day.entry(date).or_default().add(&tokens);
+    }
+}
+
+/// Per-session token usage, parsed incrementally from the transcripts the hooks report
+/// (and, after a restart, the transcripts restored tabs recorded; see `SessionsDir::new`).
+#[derive(Default)]
+pub struct UsageTracker {
+    /// transcript path -> what has been read of it. Kept after sessions close so the
+    /// daily total still counts them.
+    transcripts: Mutex<HashMap<String, Transcript>>,
+    /// session_id -> its transcripts; `/clear` and `--resume` start new ones.
+    sessions: Mutex<HashMap<String, Vec<String>>>,
+}
+
+impl UsageTracker {
+    /// Associate a transcript with a session; cheap, so it can be called on every event.
+    pub fn track(&self, session_id: &str, transcript_path: &str) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            let paths = sessions.entry(session_id.to_string()).or_default();
+            if !paths.iter().any(|p| p == transcript_path) {
+                paths.push(transcript_path.to_string());
+            }
+        }
+    }
+
+    pub fn session_usage(&self, session_id: &str) -> SessionUsage {
+        let paths = self
+            .sessions
+            .lock()
+            .ok()
+            .and_then(|sessions| sessions.get(session_id).cloned())
+            .unwrap_or_default();
+        let Ok(mut transcripts) = self.transcripts.lock() else {
+            return SessionUsage::default();
+        };
+
+        let mut usage = SessionUsage::default();
+        for path in paths {
+            let transcript = transcripts.entry(path.clone()).or_default();
+            transcript.read_new(Path::new(&path));
+            usage.total.add(&transcript.usage.total);
+            for (model, tokens) in &transcript.usage.by_model {
+                usage.by_model.entry(model.clone()).or_default().add(tokens);
+            }
+            if transcript.usage.model.is_some() {
+                usage.model = transcript.usage.model.clone();
+            }
+        }
+        usage
+    }
+
+    /// Today's usage across every tracked transcript: those the hooks reported since the
+    /// app started, plus the last conversation of each tab restored from before. Earlier
+    /// conversations of restored tabs (before a `/clear`) and tabs closed before the
+    /// restart aren't counted.
+    pub fn daily_usage(&self) -> DailyUsage {
+        let date = Local::now().format("%Y-%m-%d").to_string();
+        let paths: Vec<String> = self
+            .sessions
+            .lock()
+            .map(|sessions| sessions.values().flatten().cloned().collect())
+            .unwrap_or_default();
+
+        let mut total = TokenUsage::default();
+        if let Ok(mut transcripts) = self.transcripts.lock() {
+            for path in paths {
+                transcripts.entry(path).or_default();
+            }
+            for (path, transcript) in transcripts.iter_mut() {
+                transcript.read_new(Path::new(path));
+                if let Some(tokens) = transcript.by_day.get(&date) {
+                    total.add(tokens);
+                }
+            }
+        }
+        DailyUsage { date, total }
+    }
+}
+
+/// List prices in USD per million tokens (input, output). Cache writes cost 1.25x
+/// input and cache reads 0.1x.
+fn price_per_mtok(model: &str) -> Option<(f64, f64)> {
+    const PRICES: &[(&str, f64, f64)] = &[
+        ("claude-opus-4-1", 15.0, 75.0),
+        ("claude-opus-4-2025", 15.0, 75.0),
+        ("claude-3-opus", 15.0, 75.0),
+        ("opus", 5.0, 25.0),
+        ("sonnet", 3.0, 15.0),
+        ("claude-3-5-haiku", 0.8, 4.0),
+        ("claude-3-haiku", 0.25, 1.25),
+        ("haiku", 1.0, 5.0),
+    ];
+    PRICES
+        .iter()
+        .find(|(name, _, _)| model.contains(name))
+        .map(|&(_, input, output)| (input, output))
+}
+
+fn priced(model: &str, usage: &MessageUsage) -> TokenUsage {
+    let cost_usd = price_per_mtok(model).map_or(0.0, |(input, output)| {
+        (usage.input_tokens as f64 * input
+            + usage.output_tokens as f64 * output
+            + usage.cache_creation_input_tokens as f64 * input * 1.25
+            + usage.cache_read_input_tokens as f64 * input * 0.1)
+            / 1_000_000.0
+    });
+    TokenUsage {
+        input_tokens: usage.input_tokens,
+        output_tokens: usage.output_tokens,
+        cache_creation_input_tokens: usage.cache_creation_input_tokens,
+        cache_read_input_tokens: usage.cache_read_input_tokens,
+        cost_usd,
+    }
+}
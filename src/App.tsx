@@ -1,5 +1,6 @@
 import { useCallback, useState } from "react";
 import { AppLayout } from "./components/AppLayout";
+import { CheckoutSessionDialog } from "./components/CheckoutSessionDialog";
 import { Onboarding } from "./components/Onboarding";
 import { SessionContent } from "./components/SessionContent";
 import { Settings } from "./components/Settings";
@@ -22,7 +23,9 @@ function App() {
     defaultCommand,
     defaultWorkingDir,
     worktreeEnabled,
-    branchPrefix,
+    worktreeFailurePolicy,
+    worktreeInitSubmodules,
+    branchTemplate,
     isLoaded,
     addSession,
     removeSession,
@@ -32,7 +35,9 @@ function App() {
     setDefaultCommand,
     setDefaultWorkingDir,
     setWorktreeEnabled,
-    setBranchPrefix,
+    setWorktreeFailurePolicy,
+    setWorktreeInitSubmodules,
+    setBranchTemplate,
     notificationSound,
     setNotificationSound,
     setActivityState,
@@ -47,12 +52,17 @@ function App() {
   useUpdateToast(updater);
   const [isSettingsOpen, setIsSettingsOpen] = useState(false);
   const [sidebarCollapsed, setSidebarCollapsed] = useState(false);
+  const [isCheckoutOpen, setIsCheckoutOpen] = useState(false);
 
   const {
     handleNewSession,
     handleNewSessionWithoutWorktree,
+    handleNewSessionFromSource,
+    handleAdoptWorktree,
     handleCloseSession,
     handleRestartSession,
+    handleResumeSession,
+    handleRespondPermission,
     handleSessionStatusChange,
     handleSelectSession,
     handleTogglePanel,
@@ -66,7 +76,9 @@ function App() {
     defaultCommand,
     defaultWorkingDir,
     worktreeEnabled,
-    branchPrefix,
+    worktreeFailurePolicy,
+    worktreeInitSubmodules,
+    branchTemplate,
     isLoaded,
     onboardingCompleted,
     addSession,
@@ -77,7 +89,7 @@ function App() {
     removePersistedTab,
   });
 
-  usePolling({ sessions, notificationSound, setActivityState, updateSession });
+  usePolling({ sessions, activeSessionId, notificationSound, setActivityState, updateSession });
 
   useKeyboardShortcuts({
     sessions,
@@ -92,6 +104,11 @@ function App() {
     isSettingsOpen,
   });
 
+  // Repos to look for leftover worktrees in (see Settings)
+  const repoDirs = [
+    ...new Set([defaultWorkingDir, ...sessions.map((s) => s.gitRepoPath ?? s.workingDir)]),
+  ].filter(Boolean);
+
   const [onboardingDismissed, setOnboardingDismissed] = useState(false);
   const showOnboarding =
     !onboardingDismissed && (DEBUG_FORCE_ONBOARDING || !onboardingCompleted) && isLoaded;
@@ -129,12 +146,22 @@ function App() {
           onDefaultWorkingDirChange={setDefaultWorkingDir}
           worktreeEnabled={worktreeEnabled}
           onWorktreeEnabledChange={setWorktreeEnabled}
-          branchPrefix={branchPrefix}
-          onBranchPrefixChange={setBranchPrefix}
+          worktreeFailurePolicy={worktreeFailurePolicy}
+          onWorktreeFailurePolicyChange={setWorktreeFailurePolicy}
+          worktreeInitSubmodules={worktreeInitSubmodules}
+          onWorktreeInitSubmodulesChange={setWorktreeInitSubmodules}
+          branchTemplate={branchTemplate}
+          onBranchTemplateChange={setBranchTemplate}
           notificationSound={notificationSound}
           onNotificationSoundChange={setNotificationSound}
           updater={updater}
           activeSessionId={activeSessionId}
+          repoDirs={repoDirs}
+          knownWorktreePaths={sessions.flatMap((s) => (s.worktreePath ? [s.worktreePath] : []))}
+          onAdoptWorktree={async (worktreePath, gitRepoPath, sessionId) => {
+            await handleAdoptWorktree(worktreePath, gitRepoPath, sessionId);
+            setIsSettingsOpen(false);
+          }}
         />
       )}
 
@@ -159,8 +186,11 @@ function App() {
               position={sidebarPosition}
               onSelect={handleSelectSession}
               onNew={handleNewSession}
+              onNewFromSource={() => setIsCheckoutOpen(true)}
               onClose={handleCloseSession}
               onRestart={handleRestartSession}
+              onResume={handleResumeSession}
+              onRespondPermission={handleRespondPermission}
               onCollapse={() => setSidebarCollapsed(true)}
               getPersistedTab={getPersistedTab}
             />
@@ -190,6 +220,12 @@ function App() {
         </AppLayout>
       </div>
 
+      <CheckoutSessionDialog
+        open={isCheckoutOpen}
+        onOpenChange={setIsCheckoutOpen}
+        onCreate={handleNewSessionFromSource}
+      />
+
       <Toaster position="bottom-right" />
     </main>
   );
@@ -0,0 +1,156 @@
+import { useEffect, useState } from "react";
+import type { WorktreeSource } from "@/lib/sessions";
+import { Button } from "./ui/button";
+import {
+  Dialog,
+  DialogBody,
+  DialogClose,
+  DialogContent,
+  DialogDescription,
+  DialogFooter,
+  DialogHeader,
+  DialogTitle,
+} from "./ui/dialog";
+import { Label } from "./ui/label";
+
+interface CheckoutSessionDialogProps {
+  open: boolean;
+  onOpenChange: (open: boolean) => void;
+  onCreate: (source: WorktreeSource, sparseDirs: string[]) => void;
+}
+
+/** Start a session in a worktree of an existing branch, a commit or PR ref, or a new branch. */
+export function CheckoutSessionDialog({
+  open,
+  onOpenChange,
+  onCreate,
+}: CheckoutSessionDialogProps) {
+  const [kind, setKind] = useState<WorktreeSource["kind"]>("existing_branch");
+  const [target, setTarget] = useState("");
+  const [sparseDirs, setSparseDirs] = useState("");
+
+  useEffect(() => {
+    if (open) {
+      setTarget("");
+      setSparseDirs("");
+    }
+  }, [open]);
+
+  const needsTarget = kind !== "new_branch";
+
+  const handleCreate = () => {
+    const value = target.trim();
+    if (needsTarget && !value) return;
+    const source: WorktreeSource =
+      kind === "existing_branch"
+        ? { kind, branch: value }
+        : kind === "detached"
+          ? { kind, rev: value }
+          : { kind };
+    const dirs = sparseDirs
+      .split(/[,\n]/)
+      .map((dir) => dir.trim())
+      .filter(Boolean);
+    onCreate(source, dirs);
+    onOpenChange(false);
+  };
+
+  return (
+    <Dialog open={open} onOpenChange={onOpenChange}>
+      <DialogContent style={{ width: 480 }}>
+        <DialogClose onClick={() => onOpenChange(false)} />
+
+        <DialogHeader>
+          <DialogTitle>New Worktree Session</DialogTitle>
+          <DialogDescription>
+            Create a worktree of the default working directory's repo for the session.
+          </DialogDescription>
+        </DialogHeader>
+
+        <DialogBody>
+          <div style={{ display: "flex", flexDirection: "column", gap: 20 }}>
+            <div className="flex" style={{ gap: 8 }}>
+              {(
+                [
+                  ["existing_branch", "Branch"],
+                  ["detached", "Commit or PR ref"],
+                  ["new_branch", "New branch"],
+                ] as const
+              ).map(([value, label]) => (
+                <Button
+                  key={value}
+                  variant="outline"
+                  size="sm"
+                  className={kind === value ? "border-primary bg-primary/10 text-foreground" : ""}
+                  style={{ padding: "6px 14px" }}
+                  onClick={() => setKind(value)}
+                >
+                  {label}
+                </Button>
+              ))}
+            </div>
+
+            {needsTarget && (
+              <div>
+                <Label style={{ display: "block", marginBottom: 8 }}>
+                  {kind === "existing_branch" ? "Branch" : "Commit-ish"}
+                </Label>
+                <input
+                  className="w-full rounded-lg border border-border bg-surface-elevated text-sm text-foreground font-mono focus:border-primary focus:outline-none"
+                  style={{ padding: "8px 12px", height: 36 }}
+                  placeholder={kind === "existing_branch" ? "feature/login" : "refs/pull/42/head"}
+                  value={target}
+                  onChange={(e) => setTarget(e.target.value)}
+                  onKeyDown={(e) => {
+                    if (e.key === "Enter") handleCreate();
+                  }}
+                  autoCorrect="off"
+                  autoCapitalize="off"
+                  spellCheck={false}
+                />
+                <p className="text-xs text-foreground-subtle" style={{ marginTop: 6 }}>
+                  {kind === "existing_branch"
+                    ? "A local branch, or a remote one like origin/feature to check out locally. Closing the session keeps the branch."
+                    : "Any commit, tag or fetched ref, checked out as a detached HEAD."}
+                </p>
+              </div>
+            )}
+
+            <div>
+              <Label style={{ display: "block", marginBottom: 8 }}>
+                Directories
+                <span className="text-foreground-subtle font-normal"> (optional)</span>
+              </Label>
+              <input
+                className="w-full rounded-lg border border-border bg-surface-elevated text-sm text-foreground font-mono focus:border-primary focus:outline-none"
+                style={{ padding: "8px 12px", height: 36 }}
+                placeholder="services/api, libs/shared"
+                value={sparseDirs}
+                onChange={(e) => setSparseDirs(e.target.value)}
+                onKeyDown={(e) => {
+                  if (e.key === "Enter") handleCreate();
+                }}
+                autoCorrect="off"
+                autoCapitalize="off"
+                spellCheck={false}
+              />
+              <p className="text-xs text-foreground-subtle" style={{ marginTop: 6 }}>
+                Check out only these directories (and the files at the repo root), for large
+                monorepos. Leave empty for the whole repo.
+              </p>
+            </div>
+          </div>
+        </DialogBody>
+
+        <DialogFooter>
+          <Button variant="secondary" onClick={() => onOpenChange(false)}>
+            Cancel
+          </Button>
+          <Button onClick={handleCreate} disabled={needsTarget && !target.trim()}>
+            Create
+          </Button>
+        </DialogFooter>
+      </DialogContent>
+    </Dialog>
+  );
+}
@@ -0,0 +1,89 @@
+import { useEffect, useRef, useState } from "react";
+import { Button } from "@/components/ui/button";
+import type { SessionActivity } from "@/lib/sessions";
+
+export type PermissionResponse = "allow" | "deny";
+
+interface PermissionCardProps {
+  activity: SessionActivity;
+  /** `reason` is shown to Claude when denying. */
+  onRespond: (decision: PermissionResponse, reason?: string) => void;
+}
+
+/** Pending permission request shown under a session in the sidebar. */
+export function PermissionCard({ activity, onRespond }: PermissionCardProps) {
+  const detail = activity.command ?? activity.file_path;
+  const [denying, setDenying] = useState(false);
+  const [reason, setReason] = useState("");
+  const reasonRef = useRef<HTMLInputElement>(null);
+
+  useEffect(() => {
+    if (denying) reasonRef.current?.focus();
+  }, [denying]);
+
+  const deny = () => onRespond("deny", reason.trim() || undefined);
+
+  return (
+    <div
+      className="rounded-md border border-border bg-surface text-xs"
+      style={{ marginTop: 6, padding: "6px 8px" }}
+      onClick={(e) => e.stopPropagation()}
+    >
+      <div className="text-foreground">{activity.tool_name ?? "Permission needed"}</div>
+      {detail && (
+        <div className="font-mono truncate text-foreground-subtle" title={detail}>
+          {detail}
+        </div>
+      )}
+      {denying && (
+        <input
+          ref={reasonRef}
+          className="w-full rounded-md border border-border bg-surface-elevated text-xs text-foreground focus:border-primary focus:outline-none"
+          style={{ marginTop: 6, padding: "3px 6px", height: 24 }}
+          placeholder="Tell Claude why (optional)"
+          value={reason}
+          onChange={(e) => setReason(e.target.value)}
+          onKeyDown={(e) => {
+            if (e.key === "Enter") deny();
+            if (e.key === "Escape") setDenying(false);
+          }}
+        />
+      )}
+      <div className="flex" style={{ gap: 6, marginTop: 6 }}>
+        {denying ? (
+          <>
+            <Button size="sm" style={{ height: 22, padding: "0 8px" }} onClick={deny}>
+              Deny
+            </Button>
+            <Button
+              size="sm"
+              variant="outline"
+              style={{ height: 22, padding: "0 8px" }}
+              onClick={() => setDenying(false)}
+            >
+              Cancel
+            </Button>
+          </>
+        ) : (
+          <>
+            <Button
+              size="sm"
+              style={{ height: 22, padding: "0 8px" }}
+              onClick={() => onRespond("allow")}
+            >
+              Allow
+            </Button>
+            <Button
+              size="sm"
+              variant="outline"
+              style={{ height: 22, padding: "0 8px" }}
+              onClick={() => setDenying(true)}
+            >
+              Deny...
+            </Button>
+          </>
+        )}
+      </div>
+    </div>
+  );
+}
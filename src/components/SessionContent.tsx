@@ -59,6 +59,7 @@ export function SessionContent({
                   sessionId={session.id}
                   workingDir={session.workingDir}
                   command={session.command}
+                  resumeRequestedAt={session.resumeRequestedAt}
                   isActive={isActive}
                   onStatusChange={(status) => onStatusChange(session.id, status)}
                 />
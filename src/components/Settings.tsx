@@ -1,7 +1,13 @@
+import { invoke } from "@tauri-apps/api/core";
 import { ArrowLeft, RefreshCw, Volume2 } from "lucide-react";
 import { useEffect, useState } from "react";
 import type { UseUpdaterResult } from "@/hooks/useUpdater";
-import type { SidebarPosition } from "@/lib/sessions";
+import {
+  DEFAULT_BRANCH_TEMPLATE,
+  type RepoFile,
+  type SidebarPosition,
+  type WorktreeFailurePolicy,
+} from "@/lib/sessions";
 import { type NotificationSound, playNotificationSound, SOUND_OPTIONS } from "@/lib/sounds";
 import { WorkingDirectoryInput } from "./shared/WorkingDirectoryInput";
 import { Button } from "./ui/button";
@@ -18,12 +24,23 @@ interface SettingsProps {
   onDefaultWorkingDirChange: (dir: string) => void;
   worktreeEnabled: boolean;
   onWorktreeEnabledChange: (enabled: boolean) => void;
-  branchPrefix: string;
-  onBranchPrefixChange: (prefix: string) => void;
+  worktreeFailurePolicy: WorktreeFailurePolicy;
+  onWorktreeFailurePolicyChange: (policy: WorktreeFailurePolicy) => void;
+  worktreeInitSubmodules: boolean;
+  onWorktreeInitSubmodulesChange: (enabled: boolean) => void;
+  branchTemplate: string;
+  onBranchTemplateChange: (template: string) => void;
   notificationSound: NotificationSound;
   onNotificationSoundChange: (sound: NotificationSound) => void;
   updater: UseUpdaterResult;
   activeSessionId?: string | null;
+  repoDirs: string[];
+  knownWorktreePaths: string[];
+  onAdoptWorktree: (
+    worktreePath: string,
+    gitRepoPath: string,
+    sessionId: string | null,
+  ) => Promise<void>;
 }
 
 export function Settings({
@@ -36,30 +53,37 @@ export function Settings({
   onDefaultWorkingDirChange,
   worktreeEnabled,
   onWorktreeEnabledChange,
-  branchPrefix,
-  onBranchPrefixChange,
+  worktreeFailurePolicy,
+  onWorktreeFailurePolicyChange,
+  worktreeInitSubmodules,
+  onWorktreeInitSubmodulesChange,
+  branchTemplate,
+  onBranchTemplateChange,
   notificationSound,
   onNotificationSoundChange,
   updater,
   activeSessionId,
+  repoDirs,
+  knownWorktreePaths,
+  onAdoptWorktree,
 }: SettingsProps) {
   const [localCommand, setLocalCommand] = useState(defaultCommand);
-  const [localBranchPrefix, setLocalBranchPrefix] = useState(branchPrefix);
+  const [localBranchTemplate, setLocalBranchTemplate] = useState(branchTemplate);
 
   useEffect(() => {
     setLocalCommand(defaultCommand);
   }, [defaultCommand]);
 
   useEffect(() => {
-    setLocalBranchPrefix(branchPrefix);
-  }, [branchPrefix]);
+    setLocalBranchTemplate(branchTemplate);
+  }, [branchTemplate]);
 
   const handleBack = () => {
     if (localCommand !== defaultCommand) {
       onDefaultCommandChange(localCommand.trim());
     }
-    if (localBranchPrefix !== branchPrefix) {
-      onBranchPrefixChange(localBranchPrefix.trim());
+    if (localBranchTemplate !== branchTemplate) {
+      onBranchTemplateChange(localBranchTemplate.trim());
     }
     onBack();
   };
@@ -191,17 +215,17 @@ export function Settings({
               {worktreeEnabled && (
                 <div style={{ marginTop: 16 }}>
                   <p className="text-sm text-foreground-muted" style={{ marginBottom: 8 }}>
-                    Branch prefix
+                    Branch name template
                   </p>
                   <input
                     className="w-full rounded-lg border border-border bg-surface-elevated text-base text-foreground font-mono focus:border-primary focus:outline-none"
                     style={{ padding: "10px 12px", height: 40 }}
-                    placeholder="e.g. clutch/"
-                    value={localBranchPrefix}
-                    onChange={(e) => setLocalBranchPrefix(e.target.value)}
+                    placeholder={DEFAULT_BRANCH_TEMPLATE}
+                    value={localBranchTemplate}
+                    onChange={(e) => setLocalBranchTemplate(e.target.value)}
                     onBlur={() => {
-                      if (localBranchPrefix.trim() !== branchPrefix) {
-                        onBranchPrefixChange(localBranchPrefix.trim());
+                      if (localBranchTemplate.trim() !== branchTemplate) {
+                        onBranchTemplateChange(localBranchTemplate.trim());
                       }
                     }}
                     autoCorrect="off"
@@ -209,15 +233,60 @@ export function Settings({
                     spellCheck={false}
                   />
                   <p className="text-sm text-foreground-subtle" style={{ marginTop: 4 }}>
-                    Branch will be named{" "}
+                    Placeholders:{" "}
                     <code className="font-mono text-foreground-muted">
-                      {localBranchPrefix}brave-golden-falcon
+                      {"{user} {date} {slug} {ticket} {adjective} {color} {animal}"}
                     </code>
+                    . <code className="font-mono text-foreground-muted">{"{slug}"}</code> and{" "}
+                    <code className="font-mono text-foreground-muted">{"{ticket}"}</code> come from
+                    the session name, e.g. "ABC-123 fix login". Taken names get a numeric suffix.
                   </p>
+
+                  <p
+                    className="text-sm text-foreground-muted"
+                    style={{ marginTop: 16, marginBottom: 8 }}
+                  >
+                    If worktree creation fails
+                  </p>
+                  <select
+                    className="rounded-lg border border-border bg-surface-elevated text-base text-foreground focus:border-primary focus:outline-none"
+                    style={{ padding: "6px 12px", height: 40 }}
+                    value={worktreeFailurePolicy}
+                    onChange={(e) =>
+                      onWorktreeFailurePolicyChange(e.target.value as WorktreeFailurePolicy)
+                    }
+                  >
+                    <option value="fallback">Start in the original directory</option>
+                    <option value="retry">Retry with a new branch name</option>
+                    <option value="abort">Don't create the session</option>
+                  </select>
+
+                  <div className="flex items-center" style={{ gap: 8, marginTop: 16 }}>
+                    <Switch
+                      checked={worktreeInitSubmodules}
+                      onCheckedChange={onWorktreeInitSubmodulesChange}
+                    />
+                    <Label
+                      className="cursor-pointer"
+                      onClick={() => onWorktreeInitSubmodulesChange(!worktreeInitSubmodules)}
+                    >
+                      Check out submodules and Git LFS files
+                    </Label>
+                  </div>
                 </div>
               )}
             </SettingsSection>
 
+            <LeftoverWorktreesSection
+              repoDirs={repoDirs}
+              knownWorktreePaths={knownWorktreePaths}
+              onAdopt={onAdoptWorktree}
+            />
+
+            <RepoFilesSection repoDirs={repoDirs} />
+
+            <ClaudeHooksSection />
+
             <SettingsSection title="Updates">
               <div className="flex items-center" style={{ gap: 12 }}>
                 <Button
@@ -254,6 +323,250 @@ export function Settings({
   );
 }
 
+/** Entry of `scan_worktrees` (see git/scan.rs). */
+interface ScannedWorktree {
+  path: string;
+  branch: string | null;
+  session_id: string | null;
+  orphaned: boolean;
+  prunable: string | null;
+}
+
+interface LeftoverWorktree extends ScannedWorktree {
+  repoRoot: string;
+}
+
+function LeftoverWorktreesSection({
+  repoDirs,
+  knownWorktreePaths,
+  onAdopt,
+}: {
+  repoDirs: string[];
+  knownWorktreePaths: string[];
+  onAdopt: (worktreePath: string, gitRepoPath: string, sessionId: string | null) => Promise<void>;
+}) {
+  const [worktrees, setWorktrees] = useState<LeftoverWorktree[] | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  const scan = async () => {
+    // Dirs outside a git repo just fail to scan
+    const scans = await Promise.allSettled(
+      repoDirs.map((repo) =>
+        invoke<{ repo_root: string; worktrees: ScannedWorktree[] }>("scan_worktrees", {
+          repo,
+          knownPaths: knownWorktreePaths,
+        }),
+      ),
+    );
+    const found = new Map<string, LeftoverWorktree>();
+    for (const result of scans) {
+      if (result.status !== "fulfilled") continue;
+      for (const wt of result.value.worktrees) {
+        if (wt.orphaned) found.set(wt.path, { ...wt, repoRoot: result.value.repo_root });
+      }
+    }
+    setWorktrees([...found.values()]);
+  };
+
+  // biome-ignore lint/correctness/useExhaustiveDependencies: scan once on open
+  useEffect(() => {
+    scan();
+  }, []);
+
+  const adopt = (wt: LeftoverWorktree) => {
+    onAdopt(wt.path, wt.repoRoot, wt.session_id).catch((err) => setError(String(err)));
+  };
+
+  const remove = async (wt: LeftoverWorktree) => {
+    try {
+      const [result] = await invoke<{ success: boolean; error: string | null }[]>(
+        "prune_worktrees",
+        { gitRepoPath: wt.repoRoot, worktreePaths: [wt.path] },
+      );
+      setError(result?.success ? null : (result?.error ?? "Could not remove worktree"));
+    } catch (err) {
+      setError(String(err));
+    }
+    await scan();
+  };
+
+  return (
+    <SettingsSection
+      title="Leftover Worktrees"
+      description="Worktrees Clutch created that no open tab uses, e.g. after a crash. Open one as a new session or remove it."
+    >
+      {error && (
+        <p className="text-sm text-foreground-muted" style={{ marginBottom: 12 }}>
+          {error}
+        </p>
+      )}
+      {worktrees === null ? (
+        <p className="text-sm text-foreground-muted">Scanning...</p>
+      ) : worktrees.length === 0 ? (
+        <p className="text-sm text-foreground-muted">None found.</p>
+      ) : (
+        <div style={{ display: "flex", flexDirection: "column", gap: 8 }}>
+          {worktrees.map((wt) => (
+            <div key={wt.path} className="flex items-center" style={{ gap: 8 }}>
+              <div className="flex-1" style={{ minWidth: 0 }}>
+                <div className="text-sm text-foreground truncate">
+                  {wt.branch ?? "Detached HEAD"}
+                </div>
+                <div className="text-xs font-mono text-foreground-subtle truncate" title={wt.path}>
+                  {wt.prunable !== null ? `${wt.path} (missing)` : wt.path}
+                </div>
+              </div>
+              {wt.prunable === null && (
+                <Button variant="outline" size="sm" onClick={() => adopt(wt)}>
+                  Open
+                </Button>
+              )}
+              <Button variant="outline" size="sm" onClick={() => remove(wt)}>
+                Remove
+              </Button>
+            </div>
+          ))}
+        </div>
+      )}
+    </SettingsSection>
+  );
+}
+
+function trustLabel(trusted: boolean | null): string {
+  if (trusted === null) return " (not reviewed)";
+  return trusted ? "" : " (not trusted)";
+}
+
+function RepoFilesSection({ repoDirs }: { repoDirs: string[] }) {
+  const [files, setFiles] = useState<RepoFile[] | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  const load = async () => {
+    const found = new Map<string, RepoFile>();
+    for (const dir of repoDirs) {
+      const repoFiles = await invoke<RepoFile[]>("repo_files", { dir }).catch(() => []);
+      for (const file of repoFiles) found.set(file.path, file);
+    }
+    setFiles([...found.values()]);
+  };
+
+  // biome-ignore lint/correctness/useExhaustiveDependencies: load once on open
+  useEffect(() => {
+    load();
+  }, []);
+
+  const setTrust = async (file: RepoFile, trusted: boolean) => {
+    try {
+      await invoke("set_repo_file_trust", { path: file.path, content: file.content, trusted });
+      setError(null);
+    } catch (err) {
+      setError(String(err));
+    }
+    await load();
+  };
+
+  return (
+    <SettingsSection
+      title="Repo Files"
+      description="Scripts and permission rules in your repos' .clutch folder only apply once you trust them. Editing one asks again."
+    >
+      {error && (
+        <p className="text-sm text-foreground-muted" style={{ marginBottom: 12 }}>
+          {error}
+        </p>
+      )}
+      {files === null ? (
+        <p className="text-sm text-foreground-muted">Checking...</p>
+      ) : files.length === 0 ? (
+        <p className="text-sm text-foreground-muted">None found.</p>
+      ) : (
+        <div style={{ display: "flex", flexDirection: "column", gap: 8 }}>
+          {files.map((file) => (
+            <div key={file.path} className="flex items-center" style={{ gap: 8 }}>
+              <div className="flex-1" style={{ minWidth: 0 }}>
+                <div className="text-sm text-foreground truncate">
+                  {file.name}
+                  <span className="text-foreground-subtle">
+                    {trustLabel(file.trusted)}
+                  </span>
+                </div>
+                <div
+                  className="text-xs font-mono text-foreground-subtle truncate"
+                  title={file.path}
+                >
+                  {file.path}
+                </div>
+              </div>
+              <Button variant="outline" size="sm" onClick={() => setTrust(file, !file.trusted)}>
+                {file.trusted ? "Revoke" : "Trust"}
+              </Button>
+            </div>
+          ))}
+        </div>
+      )}
+    </SettingsSection>
+  );
+}
+
+/** Clutch's entries in `~/.claude/settings.json` (see hooks_config.rs). */
+interface HooksStatus {
+  settings_path: string;
+  events: { event: string; status: "installed" | "missing" | "modified" | "duplicate" }[];
+  installed: boolean;
+  disabled: boolean;
+}
+
+function describeHooks(status: HooksStatus): string {
+  if (status.installed) return "Installed.";
+  const problems = status.events.filter((e) => e.status !== "installed");
+  if (problems.length === status.events.length && problems.every((e) => e.status === "missing")) {
+    return status.disabled ? "Uninstalled." : "Not installed.";
+  }
+  return problems.map((e) => `${e.event}: ${e.status}`).join(", ");
+}
+
+function ClaudeHooksSection() {
+  const [status, setStatus] = useState<HooksStatus | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  const run = (command: "hooks_status" | "install_hooks" | "uninstall_hooks") => {
+    invoke<HooksStatus>(command)
+      .then((next) => {
+        setStatus(next);
+        setError(null);
+      })
+      .catch((err) => setError(String(err)));
+  };
+
+  // biome-ignore lint/correctness/useExhaustiveDependencies: load once on open
+  useEffect(() => {
+    run("hooks_status");
+  }, []);
+
+  const needsRepair = status?.events.some(
+    (e) => e.status === "modified" || e.status === "duplicate",
+  );
+
+  return (
+    <SettingsSection
+      title="Claude Code Hooks"
+      description="Hooks in ~/.claude/settings.json that report session activity to Clutch. Your own hooks are left alone."
+    >
+      <p className="text-sm text-foreground-muted" style={{ marginBottom: 12 }}>
+        {error ?? (status ? describeHooks(status) : "Checking...")}
+      </p>
+      <div className="flex" style={{ gap: 8 }}>
+        <Button variant="outline" size="sm" onClick={() => run("install_hooks")}>
+          {needsRepair ? "Repair" : "Install"}
+        </Button>
+        <Button variant="outline" size="sm" onClick={() => run("uninstall_hooks")}>
+          Uninstall
+        </Button>
+      </div>
+    </SettingsSection>
+  );
+}
+
 function SettingsSection({
   title,
   description,
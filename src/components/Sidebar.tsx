@@ -1,4 +1,14 @@
-import { ChevronsLeft, ChevronsRight, GitBranch, Plus, RotateCw, X } from "lucide-react";
+import {
+  ChevronsLeft,
+  ChevronsRight,
+  GitBranch,
+  History,
+  Plus,
+  RotateCw,
+  TriangleAlert,
+  X,
+} from "lucide-react";
+import { PermissionCard, type PermissionResponse } from "@/components/PermissionCard";
 import { Button } from "@/components/ui/button";
 import type { PersistedTab } from "@/lib/persisted-tabs";
 import type { Session, SidebarPosition } from "@/lib/sessions";
@@ -11,29 +21,63 @@ interface SidebarProps {
   position: SidebarPosition;
   onSelect: (sessionId: string) => void;
   onNew: () => void;
+  onNewFromSource: () => void;
   onClose: (sessionId: string) => void;
   onRestart: (sessionId: string) => void;
+  onResume: (sessionId: string) => void;
+  onRespondPermission: (sessionId: string, decision: PermissionResponse, reason?: string) => void;
   onCollapse?: () => void;
   getPersistedTab: (tabId: string | undefined) => PersistedTab | undefined;
 }
 
+/** Tooltip listing files this session shares with other sessions, if any. */
+function getOverlapTitle(
+  session: Session,
+  sessions: Session[],
+  getPersistedTab: SidebarProps["getPersistedTab"],
+): string | undefined {
+  if (!session.overlaps?.length) return undefined;
+  return session.overlaps
+    .map(({ sessionId, files }) => {
+      const other = sessions.find((s) => s.id === sessionId);
+      const name = other ? sessionDisplayName(other, getPersistedTab(other.id)) : sessionId;
+      const paths = files.map((f) => (f.hunks_overlap ? `${f.path} (same lines)` : f.path));
+      return `Also edited in ${name}: ${paths.join(", ")}`;
+    })
+    .join("\n");
+}
+
 function getActivityDot(session: Session): {
   color: string;
   animation?: string;
+  title?: string;
 } {
   if (session.status === "exited") {
     return { color: "transparent" };
   }
+  const toolName = session.activity?.tool_name;
   switch (session.activityState) {
-    case "running":
+    case "working":
       return {
         color: "#22c55e",
         animation: "pulse-green 2s ease-in-out infinite",
+        title: toolName ? `Running ${toolName}` : "Working",
+      };
+    case "waiting_input":
+      return { color: "#22c55e", title: "Waiting for input" };
+    case "compacting":
+      return {
+        color: "#f59e0b",
+        animation: "pulse-green 2s ease-in-out infinite",
+        title: "Compacting conversation",
+      };
+    case "waiting_permission":
+      return {
+        color: "#ef4444",
+        title:
+          session.activity?.message ??
+          (toolName ? `Needs permission for ${toolName}` : "Needs input"),
       };
-    case "finished":
-      return { color: "#22c55e" };
-    case "needs_input":
-      return { color: "#ef4444" };
     default:
       return { color: "transparent" };
   }
@@ -45,8 +89,11 @@ export function Sidebar({
   position,
   onSelect,
   onNew,
+  onNewFromSource,
   onClose,
   onRestart,
+  onResume,
+  onRespondPermission,
   onCollapse,
   getPersistedTab,
 }: SidebarProps) {
@@ -71,8 +118,11 @@ export function Sidebar({
       activeSessionId={activeSessionId}
       onSelect={onSelect}
       onNew={onNew}
+      onNewFromSource={onNewFromSource}
       onClose={onClose}
       onRestart={onRestart}
+      onResume={onResume}
+      onRespondPermission={onRespondPermission}
       onCollapse={onCollapse}
       getPersistedTab={getPersistedTab}
     />
@@ -84,8 +134,11 @@ function VerticalSidebar({
   activeSessionId,
   onSelect,
   onNew,
+  onNewFromSource,
   onClose,
   onRestart,
+  onResume,
+  onRespondPermission,
   onCollapse,
   getPersistedTab,
 }: Omit<SidebarProps, "position">) {
@@ -118,6 +171,7 @@ function VerticalSidebar({
       <div className="flex-1 overflow-y-auto" style={{ padding: 6 }}>
         {sessions.map((session) => {
           const dot = getActivityDot(session);
+          const overlapTitle = getOverlapTitle(session, sessions, getPersistedTab);
           return (
             <div
               key={session.id}
@@ -132,6 +186,7 @@ function VerticalSidebar({
             >
               <div
                 className="rounded-full flex-shrink-0"
+                title={dot.title}
                 style={{
                   width: 9,
                   height: 9,
@@ -157,14 +212,44 @@ function VerticalSidebar({
                   >
                     <GitBranch style={{ width: 11, height: 11, flexShrink: 0 }} />
                     <span className="truncate">{session.gitBranch}</span>
+                    {session.gitDirty && <span title="Uncommitted changes">•</span>}
+                    {overlapTitle && (
+                      <span title={overlapTitle} style={{ color: "#f59e0b", display: "flex" }}>
+                        <TriangleAlert style={{ width: 11, height: 11, flexShrink: 0 }} />
+                      </span>
+                    )}
                   </div>
                 )}
+                {session.permissionPending &&
+                  session.activity?.event === "PermissionRequest" && (
+                    <PermissionCard
+                      activity={session.activity}
+                      onRespond={(decision, reason) =>
+                        onRespondPermission(session.id, decision, reason)
+                      }
+                    />
+                  )}
               </div>
 
               <div
                 className="flex items-center opacity-0 group-hover:opacity-100 transition-opacity"
                 style={{ gap: 2, marginLeft: 4 }}
               >
+                {session.status === "exited" && session.claudeSession && (
+                  <Button
+                    variant="ghost"
+                    size="icon"
+                    className="text-foreground-subtle hover:text-foreground hover:bg-surface-hover"
+                    style={{ width: 22, height: 22 }}
+                    onClick={(e) => {
+                      e.stopPropagation();
+                      onResume(session.id);
+                    }}
+                    title="Resume conversation"
+                  >
+                    <History className="h-3 w-3" />
+                  </Button>
+                )}
                 {session.status === "exited" && (
                   <Button
                     variant="ghost"
@@ -209,6 +294,15 @@ function VerticalSidebar({
           <Plus className="h-4 w-4" />
           New Session
         </Button>
+        <Button
+          variant="ghost"
+          className="w-full justify-start text-foreground-muted hover:text-foreground"
+          style={{ padding: "8px 10px", gap: 8, height: "auto" }}
+          onClick={onNewFromSource}
+        >
+          <GitBranch className="h-4 w-4" />
+          New Worktree...
+        </Button>
       </div>
     </div>
   );
@@ -238,6 +332,7 @@ function HorizontalSidebar({
     >
       {sessions.map((session) => {
         const dot = getActivityDot(session);
+        const overlapTitle = getOverlapTitle(session, sessions, getPersistedTab);
         return (
           <div
             key={session.id}
@@ -252,6 +347,7 @@ function HorizontalSidebar({
           >
             <div
               className="rounded-full flex-shrink-0"
+              title={dot.title}
               style={{
                 width: 8,
                 height: 8,
@@ -274,6 +370,12 @@ function HorizontalSidebar({
                 <span className="truncate" style={{ maxWidth: 80 }}>
                   {session.gitBranch}
                 </span>
+                {session.gitDirty && <span title="Uncommitted changes">•</span>}
+                {overlapTitle && (
+                  <span title={overlapTitle} style={{ color: "#f59e0b", display: "flex" }}>
+                    <TriangleAlert style={{ width: 10, height: 10, flexShrink: 0 }} />
+                  </span>
+                )}
               </span>
             )}
             <Button
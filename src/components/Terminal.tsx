@@ -14,6 +14,7 @@ export interface TerminalProps {
   sessionId: string;
   workingDir: string;
   command?: string;
+  resumeRequestedAt?: number;
   isActive: boolean;
   onStatusChange?: (status: "running" | "exited") => void;
   backgroundColor?: string;
@@ -24,6 +25,7 @@ export function Terminal({
   sessionId,
   workingDir,
   command,
+  resumeRequestedAt,
   isActive,
   onStatusChange,
   backgroundColor = "#000000",
@@ -47,7 +49,7 @@ export function Terminal({
     terminalRef.current?.write(data);
   }, []);
 
-  const { spawn, write, resize } = usePty({
+  const { spawn, respawn, write, resize } = usePty({
     sessionId,
     onData: handleData,
     onExit: () => {
@@ -57,6 +59,18 @@ export function Terminal({
     },
   });
 
+  // Relaunch the session's last Claude conversation when asked to resume
+  // biome-ignore lint/correctness/useExhaustiveDependencies: only reacts to new resume requests
+  useEffect(() => {
+    if (!resumeRequestedAt) return;
+    terminalRef.current?.writeln("\r\n\x1b[90mResuming conversation...\x1b[0m\r\n");
+    respawn(undefined, undefined, true)
+      .then(() => onStatusChange?.("running"))
+      .catch((err) => {
+        terminalRef.current?.writeln(`\x1b[31mFailed to resume: ${err}\x1b[0m`);
+      });
+  }, [resumeRequestedAt]);
+
   // Fit terminal when becoming active
   useEffect(() => {
     if (isActive && fitAddonRef.current && terminalRef.current) {
@@ -1,12 +1,37 @@
 import { invoke } from "@tauri-apps/api/core";
-import { useEffect, useRef } from "react";
-import { ACTIVITY_POLL_INTERVAL, GIT_BRANCH_POLL_INTERVAL } from "../lib/config";
-import type { ClaudeActivityState, Session } from "../lib/sessions";
+import { listen } from "@tauri-apps/api/event";
+import { useCallback, useEffect, useRef } from "react";
+import { OVERLAP_CHECK_INTERVAL } from "../lib/config";
+import type {
+  ClaudeActivityState,
+  Session,
+  SessionActivity,
+  SessionOverlap,
+} from "../lib/sessions";
 import type { NotificationSound } from "../lib/sounds";
 import { playNotificationSound } from "../lib/sounds";
 
+interface GitState {
+  branch: string | null;
+  head: string | null;
+  dirty: boolean;
+}
+
+interface GitStateChangedPayload extends GitState {
+  session_id: string;
+}
+
+interface SessionActivityPayload extends SessionActivity {
+  session_id: string;
+}
+
+interface PermissionPromptClosedPayload {
+  session_id: string;
+}
+
 interface UsePollingOptions {
   sessions: Session[];
+  activeSessionId: string | null;
   notificationSound: NotificationSound;
   setActivityState: (sessionId: string, state: ClaudeActivityState) => void;
   updateSession: (sessionId: string, updates: Partial<Session>) => void;
@@ -14,75 +39,167 @@ interface UsePollingOptions {
 
 export function usePolling({
   sessions,
+  activeSessionId,
   notificationSound,
   setActivityState,
   updateSession,
 }: UsePollingOptions) {
   const notificationSoundRef = useRef(notificationSound);
   notificationSoundRef.current = notificationSound;
+  const activeSessionIdRef = useRef(activeSessionId);
+  activeSessionIdRef.current = activeSessionId;
 
-  // Poll session activity from Rust backend
+  // Session activity is pushed by the backend as hooks deliver events
   const lastSeenRef = useRef<Record<string, string>>({});
-  useEffect(() => {
-    const sessionIds = sessions.filter((s) => s.status === "running").map((s) => s.id);
-    if (sessionIds.length === 0) return;
+  const lastStateRef = useRef<Record<string, ClaudeActivityState>>({});
+  // `live` is set for events just delivered, whose permission request may still be
+  // waiting for the app (see hook_socket.rs)
+  const applyActivity = useCallback(
+    (sessionId: string, activity: SessionActivity, live: boolean) => {
+      const key = JSON.stringify(activity);
+      if (key === lastSeenRef.current[sessionId]) return;
+      lastSeenRef.current[sessionId] = key;
 
-    const poll = async () => {
-      try {
-        const statuses = await invoke<Record<string, string>>("poll_session_activity", {
-          sessionIds,
+      let permissionPending =
+        live && activity.event === "PermissionRequest" && !activity.permission_decision;
+      // Someone looking at the tab answers in its terminal, so let Claude prompt right away
+      if (permissionPending && sessionId === activeSessionIdRef.current && document.hasFocus()) {
+        permissionPending = false;
+        invoke("respond_permission", { sessionId, decision: "ask", reason: null }).catch(() => {
+          // Not waiting on the hook (already answered or timed out)
         });
-        const lastSeen = lastSeenRef.current;
-
-        for (const [sessionId, content] of Object.entries(statuses)) {
-          if (content === lastSeen[sessionId]) continue;
-          lastSeen[sessionId] = content;
-
-          if (content === "UserPromptSubmit" || content === "PreToolUse") {
-            setActivityState(sessionId, "running");
-          } else if (content === "Stop") {
-            setActivityState(sessionId, "finished");
-          } else if (content === "PermissionRequest") {
-            setActivityState(sessionId, "needs_input");
-            playNotificationSound(notificationSoundRef.current);
-          }
-        }
-      } catch {
-        // Ignore poll errors
       }
+
+      const { claude_session_id, transcript_path } = activity;
+      if (activity.event === "UserPromptSubmit" && claude_session_id) {
+        const claudeSession = {
+          session_id: claude_session_id,
+          transcript_path,
+          updated_at: activity.timestamp,
+        };
+        updateSession(sessionId, { activity, permissionPending, claudeSession });
+      } else {
+        updateSession(sessionId, { activity, permissionPending });
+      }
+
+      const previous = lastStateRef.current[sessionId];
+      lastStateRef.current[sessionId] = activity.state;
+      setActivityState(sessionId, activity.state);
+      if (activity.state === "waiting_permission" && previous !== "waiting_permission") {
+        playNotificationSound(notificationSoundRef.current);
+      }
+    },
+    [setActivityState, updateSession],
+  );
+
+  useEffect(() => {
+    const unlisten = listen<SessionActivityPayload>("session-activity", (event) => {
+      const { session_id, ...activity } = event.payload;
+      applyActivity(session_id, activity, true);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
     };
+  }, [applyActivity]);
 
-    const interval = setInterval(poll, ACTIVITY_POLL_INTERVAL);
-    return () => clearInterval(interval);
-  }, [sessions, setActivityState]);
+  // The app stopped waiting for an answer (answered, or left to Claude's own prompt)
+  useEffect(() => {
+    const unlisten = listen<PermissionPromptClosedPayload>("permission-prompt-closed", (event) => {
+      updateSession(event.payload.session_id, { permissionPending: false });
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [updateSession]);
 
-  // Poll git branches for running sessions
+  // Catch up on events delivered before listening started (or written to status files)
+  const runningKey = sessions
+    .filter((s) => s.status === "running")
+    .map((s) => s.id)
+    .join("\n");
   useEffect(() => {
-    const runningSessions = sessions.filter((s) => s.status === "running" && s.workingDir);
-    if (runningSessions.length === 0) return;
+    if (!runningKey) return;
+    invoke<Record<string, SessionActivity>>("poll_session_activity", {
+      sessionIds: runningKey.split("\n"),
+    })
+      .then((activities) => {
+        for (const [sessionId, activity] of Object.entries(activities)) {
+          applyActivity(sessionId, activity, false);
+        }
+      })
+      .catch(() => {
+        // Ignore catch-up errors
+      });
+  }, [runningKey, applyActivity]);
 
-    const poll = async () => {
-      try {
-        const sessionDirs: Record<string, string> = {};
-        for (const s of runningSessions) {
-          sessionDirs[s.id] = s.workingDir;
+  // Git state is pushed by the backend's watcher whenever it changes
+  useEffect(() => {
+    const unlisten = listen<GitStateChangedPayload>("git-state-changed", (event) => {
+      const { session_id, branch, dirty } = event.payload;
+      updateSession(session_id, { gitBranch: branch ?? undefined, gitDirty: dirty });
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [updateSession]);
+
+  // Keep the watched set in sync with running sessions (only when it actually changes)
+  const watchKey = sessions
+    .filter((s) => s.status === "running" && s.workingDir)
+    .map((s) => `${s.id}\t${s.workingDir}`)
+    .join("\n");
+  useEffect(() => {
+    const sessionDirs: Record<string, string> = {};
+    for (const line of watchKey ? watchKey.split("\n") : []) {
+      const [id, dir] = line.split("\t");
+      sessionDirs[id] = dir;
+    }
+
+    invoke<Record<string, GitState>>("watch_git_state", { sessions: sessionDirs })
+      .then((states) => {
+        for (const [sessionId, { branch, dirty }] of Object.entries(states)) {
+          updateSession(sessionId, { gitBranch: branch ?? undefined, gitDirty: dirty });
         }
-        const branches = await invoke<Record<string, string>>("get_git_branches", {
+      })
+      .catch(() => {
+        // Ignore watch errors
+      });
+  }, [watchKey, updateSession]);
+
+  // Check for files edited by more than one session of the same repo
+  const lastOverlapsRef = useRef<Record<string, string>>({});
+  useEffect(() => {
+    const sessionDirs: Record<string, string> = {};
+    for (const line of watchKey ? watchKey.split("\n") : []) {
+      const [id, dir] = line.split("\t");
+      sessionDirs[id] = dir;
+    }
+    if (Object.keys(sessionDirs).length < 2) return;
+
+    const check = async () => {
+      try {
+        const overlaps = await invoke<SessionOverlap[]>("detect_worktree_overlaps", {
           sessions: sessionDirs,
         });
-        for (const [sessionId, branch] of Object.entries(branches)) {
-          const session = sessions.find((s) => s.id === sessionId);
-          if (session && session.gitBranch !== branch) {
-            updateSession(sessionId, { gitBranch: branch });
-          }
+        const bySession: Record<string, NonNullable<Session["overlaps"]>> = {};
+        for (const { session_a, session_b, files } of overlaps) {
+          (bySession[session_a] ??= []).push({ sessionId: session_b, files });
+          (bySession[session_b] ??= []).push({ sessionId: session_a, files });
+        }
+        const lastOverlaps = lastOverlapsRef.current;
+        for (const sessionId of Object.keys(sessionDirs)) {
+          const key = JSON.stringify(bySession[sessionId] ?? []);
+          if (key === lastOverlaps[sessionId]) continue;
+          lastOverlaps[sessionId] = key;
+          updateSession(sessionId, { overlaps: bySession[sessionId] });
         }
       } catch {
-        // Ignore poll errors
+        // Ignore check errors
       }
     };
 
-    poll(); // Initial fetch
-    const interval = setInterval(poll, GIT_BRANCH_POLL_INTERVAL);
+    check();
+    const interval = setInterval(check, OVERLAP_CHECK_INTERVAL);
     return () => clearInterval(interval);
-  }, [sessions, updateSession]);
+  }, [watchKey, updateSession]);
 }
@@ -80,7 +80,7 @@ export function usePty({ sessionId, onData, onExit }: UsePtyOptions) {
   );
 
   const respawn = useCallback(
-    async (workingDir?: string, command?: string) => {
+    async (workingDir?: string, command?: string, resume = false) => {
       if (!lastSpawnArgs.current) return;
 
       isSpawned.current = false;
@@ -95,6 +95,7 @@ export function usePty({ sessionId, onData, onExit }: UsePtyOptions) {
         rows,
         workingDir: dir ?? null,
         command: cmd ?? null,
+        resume,
       });
 
       isSpawned.current = true;
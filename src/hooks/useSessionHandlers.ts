@@ -1,10 +1,60 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { nanoid } from "nanoid";
 import { useCallback, useEffect, useRef, useState } from "react";
 import type { PanelImperativeHandle } from "react-resizable-panels";
+import { toast } from "sonner";
 import type { PersistedTab } from "../lib/persisted-tabs";
-import type { Session } from "../lib/sessions";
-import { generateBranchName } from "../lib/sessions";
+import type {
+  RepoFile,
+  Session,
+  WorktreeFailurePolicy,
+  WorktreeSource,
+} from "../lib/sessions";
+
+/** Structured worktree error from `setup_session_worktree` (see git/error.rs). */
+interface WorktreeSetupError {
+  kind: string;
+  message: string;
+}
+
+interface ScriptResult {
+  success: boolean;
+  error: string | null;
+}
+
+interface WorktreeScriptOutputPayload {
+  worktree_id: string;
+  stage: "submodules" | "lfs" | "setup";
+  line: string;
+}
+
+const SCRIPT_STAGE_LABELS: Record<WorktreeScriptOutputPayload["stage"], string> = {
+  submodules: "Checking out submodules",
+  lfs: "Fetching Git LFS files",
+  setup: "Running setup script",
+};
+
+/** Longest part of a repo file shown when asking to trust it. */
+const TRUST_PREVIEW_LENGTH = 1200;
+
+// A cloned repo can ship files that make Clutch run code, so ask about each new or
+// changed one before the session uses it
+async function confirmRepoFiles(workingDir: string) {
+  const files = await invoke<RepoFile[]>("repo_files", { dir: workingDir }).catch(() => []);
+  for (const file of files.filter((f) => f.trusted === null)) {
+    const { ask } = await import("@tauri-apps/plugin-dialog");
+    const preview =
+      file.content.length > TRUST_PREVIEW_LENGTH
+        ? `${file.content.slice(0, TRUST_PREVIEW_LENGTH)}\n…`
+        : file.content;
+    const trusted = await ask(
+      `This repo's ${file.name} ${file.description}:\n\n${preview}\n\nOnly trust it if you know what it does. You'll be asked again if it changes.`,
+      { title: "Trust Repo File", kind: "warning", okLabel: "Trust", cancelLabel: "Don't Trust" },
+    );
+    await invoke("set_repo_file_trust", { path: file.path, content: file.content, trusted });
+  }
+}
 
 interface UseSessionHandlersOptions {
   sessions: Session[];
@@ -12,7 +62,9 @@ interface UseSessionHandlersOptions {
   defaultCommand: string;
   defaultWorkingDir: string;
   worktreeEnabled: boolean;
-  branchPrefix: string;
+  worktreeFailurePolicy: WorktreeFailurePolicy;
+  worktreeInitSubmodules: boolean;
+  branchTemplate: string;
   isLoaded: boolean;
   onboardingCompleted: boolean;
   addSession: (session: Session) => void;
@@ -29,7 +81,9 @@ export function useSessionHandlers({
   defaultCommand,
   defaultWorkingDir,
   worktreeEnabled,
-  branchPrefix,
+  worktreeFailurePolicy,
+  worktreeInitSubmodules,
+  branchTemplate,
   isLoaded,
   onboardingCompleted,
   addSession,
@@ -50,7 +104,14 @@ export function useSessionHandlers({
   const panelRefs = useRef<Map<string, PanelImperativeHandle | null>>(new Map());
 
   const handleCreateSession = useCallback(
-    async (name: string, workingDir: string, command: string, skipWorktree = false) => {
+    async (
+      name: string,
+      workingDir: string,
+      command: string,
+      skipWorktree = false,
+      source?: WorktreeSource,
+      sparseDirs: string[] = [],
+    ) => {
       // Single ID used for both Session.id and PersistedTab.id.
       // Stable across restarts so CLUTCH_SESSION_ID and status dirs survive.
       const id = nanoid();
@@ -64,18 +125,52 @@ export function useSessionHandlers({
           s.originalWorkingDir === workingDir || (!s.worktreePath && s.workingDir === workingDir),
       );
 
-      if (!skipWorktree && worktreeEnabled && workingDir && hasExistingSessionForRepo) {
+      // Checking out a given branch or commit always needs a worktree of its own
+      const wantsWorktree =
+        source !== undefined || (!skipWorktree && worktreeEnabled && hasExistingSessionForRepo);
+
+      if (workingDir) {
+        await confirmRepoFiles(workingDir);
+      }
+
+      if (workingDir && wantsWorktree) {
+        // Submodule clones and setup scripts can be slow: show their latest output line
+        const unlistenOutput = await listen<WorktreeScriptOutputPayload>(
+          "worktree-script-output",
+          (event) => {
+            const { worktree_id, stage, line } = event.payload;
+            if (worktree_id !== id || !line.trim()) return;
+            toast.loading(SCRIPT_STAGE_LABELS[stage], { id, description: line.trim() });
+          },
+        );
         try {
-          const branchName = branchPrefix + generateBranchName();
+          // Only a new branch needs a name; the others check out what `source` names
+          const branchName =
+            source && source.kind !== "new_branch"
+              ? ""
+              : await invoke<string>("generate_branch_name", {
+                  workingDir,
+                  template: branchTemplate,
+                  description: name || null,
+                  ticket: null,
+                });
           const result = await invoke<{
             effective_dir: string;
             worktree_path: string | null;
             git_repo_path: string | null;
+            error: WorktreeSetupError | null;
+            submodules: ScriptResult | null;
+            lfs: ScriptResult | null;
+            setup: ScriptResult | null;
           }>("setup_session_worktree", {
             worktreeId: id,
             branchName,
             workingDir,
             location: "home",
+            source: source ?? null,
+            sparsePaths: sparseDirs.length > 0 ? sparseDirs : null,
+            failurePolicy: worktreeFailurePolicy,
+            initSubmodules: worktreeInitSubmodules,
           });
           effectiveDir = result.effective_dir;
           worktreePath = result.worktree_path ?? undefined;
@@ -83,8 +178,44 @@ export function useSessionHandlers({
           if (worktreePath) {
             originalWorkingDir = workingDir;
           }
-        } catch {
-          // Fallback to original dir
+          if (result.error) {
+            const { message } = await import("@tauri-apps/plugin-dialog");
+            await message(
+              `Could not create worktree:\n${result.error.message}\n\nThe session will start in:\n${effectiveDir}`,
+              { title: "Worktree Setup", kind: "warning" },
+            );
+          }
+          const failedSteps: [string, ScriptResult | null][] = [
+            ["Submodule checkout", result.submodules],
+            ["Git LFS checkout", result.lfs],
+            ["Worktree setup script", result.setup],
+          ];
+          for (const [step, stepResult] of failedSteps) {
+            if (!stepResult || stepResult.success) continue;
+            const { message } = await import("@tauri-apps/plugin-dialog");
+            await message(
+              `${step} failed:\n${stepResult.error ?? "unknown error"}\n\nThe session will start in:\n${effectiveDir}`,
+              { title: "Worktree Setup", kind: "warning" },
+            );
+          }
+        } catch (e) {
+          const { message } = await import("@tauri-apps/plugin-dialog");
+          const reason = (e as WorktreeSetupError)?.message ?? String(e);
+          if (worktreeFailurePolicy !== "fallback") {
+            await message(`Could not create worktree:\n${reason}`, {
+              title: "Worktree Setup",
+              kind: "error",
+            });
+            return;
+          }
+          // Fallback to original dir (e.g. the branch name template is invalid)
+          await message(
+            `Could not create worktree:\n${reason}\n\nThe session will start in:\n${workingDir}`,
+            { title: "Worktree Setup", kind: "warning" },
+          );
+        } finally {
+          unlistenOutput();
+          toast.dismiss(id);
         }
       }
 
@@ -98,7 +229,7 @@ export function useSessionHandlers({
         worktreePath,
         gitRepoPath,
         originalWorkingDir,
-        activityState: "idling",
+        activityState: "idle",
       };
       addSession(session);
       addPersistedTab({
@@ -111,7 +242,55 @@ export function useSessionHandlers({
         originalWorkingDir,
       });
     },
-    [addSession, addPersistedTab, sessions, worktreeEnabled, branchPrefix],
+    [
+      addSession,
+      addPersistedTab,
+      sessions,
+      worktreeEnabled,
+      worktreeFailurePolicy,
+      worktreeInitSubmodules,
+      branchTemplate,
+    ],
+  );
+
+  // Open a worktree left behind by an earlier session (see `scan_worktrees`) as a new tab
+  const handleAdoptWorktree = useCallback(
+    async (worktreePath: string, gitRepoPath: string, previousSessionId: string | null) => {
+      // Reusing the old ID keeps its saved Claude conversation (see resume.rs)
+      const id =
+        previousSessionId && !sessions.some((s) => s.id === previousSessionId)
+          ? previousSessionId
+          : nanoid();
+      const result = await invoke<{ effective_dir: string }>("adopt_worktree", {
+        worktreeId: id,
+        worktreePath,
+        gitRepoPath,
+      });
+      const session: Session = {
+        id,
+        name: "",
+        workingDir: result.effective_dir,
+        command: defaultCommand || undefined,
+        status: "running",
+        createdAt: Date.now(),
+        worktreePath,
+        gitRepoPath,
+        originalWorkingDir: gitRepoPath,
+        activityState: "idle",
+      };
+      addSession(session);
+      addPersistedTab({
+        id,
+        workingDir: result.effective_dir,
+        command: defaultCommand || undefined,
+        createdAt: Date.now(),
+        worktreePath,
+        gitRepoPath,
+        originalWorkingDir: gitRepoPath,
+      });
+      setActiveSession(id);
+    },
+    [sessions, defaultCommand, addSession, addPersistedTab, setActiveSession],
   );
 
   const handleNewSession = useCallback(() => {
@@ -122,6 +301,13 @@ export function useSessionHandlers({
     handleCreateSession("", defaultWorkingDir, defaultCommand, true);
   }, [handleCreateSession, defaultWorkingDir, defaultCommand]);
 
+  const handleNewSessionFromSource = useCallback(
+    (source: WorktreeSource, sparseDirs: string[]) => {
+      handleCreateSession("", defaultWorkingDir, defaultCommand, false, source, sparseDirs);
+    },
+    [handleCreateSession, defaultWorkingDir, defaultCommand],
+  );
+
   // Auto-create a session on startup (but not until onboarding is done,
   // so the user has a chance to pick a working directory first).
   const hasAutoCreatedRef = useRef(false);
@@ -206,6 +392,30 @@ export function useSessionHandlers({
     [sessions, handleCloseSession, handleCreateSession],
   );
 
+  // Relaunch an exited tab's last Claude conversation in place, keeping its worktree
+  const handleResumeSession = useCallback(
+    (sessionId: string) => {
+      updateSession(sessionId, { resumeRequestedAt: Date.now() });
+    },
+    [updateSession],
+  );
+
+  // Answer a permission prompt without opening the tab (see permission_prompts.rs)
+  const handleRespondPermission = useCallback(
+    async (sessionId: string, decision: "allow" | "deny", reason?: string) => {
+      updateSession(sessionId, { permissionPending: false });
+      try {
+        await invoke("respond_permission", { sessionId, decision, reason: reason ?? null });
+      } catch {
+        // Released just before the click (timed out or answered in the terminal)
+        toast("Answer in the terminal", {
+          description: "The permission request was handed back to Claude's own prompt.",
+        });
+      }
+    },
+    [updateSession],
+  );
+
   const handleSessionStatusChange = useCallback(
     (sessionId: string, status: "running" | "exited") => {
       updateSession(sessionId, { status });
@@ -216,8 +426,16 @@ export function useSessionHandlers({
   const handleSelectSession = useCallback(
     (sessionId: string) => {
       setActiveSession(sessionId);
+      // Opening the tab hands a waiting permission request back to Claude's own prompt
+      const session = sessions.find((s) => s.id === sessionId);
+      if (session?.permissionPending) {
+        updateSession(sessionId, { permissionPending: false });
+        invoke("respond_permission", { sessionId, decision: "ask", reason: null }).catch(() => {
+          // Not waiting on the hook (already answered or timed out)
+        });
+      }
     },
-    [setActiveSession],
+    [sessions, setActiveSession, updateSession],
   );
 
   const handleTogglePanel = useCallback(() => {
@@ -245,8 +463,12 @@ export function useSessionHandlers({
   return {
     handleNewSession,
     handleNewSessionWithoutWorktree,
+    handleNewSessionFromSource,
+    handleAdoptWorktree,
     handleCloseSession,
     handleRestartSession,
+    handleResumeSession,
+    handleRespondPermission,
     handleSessionStatusChange,
     handleSelectSession,
     handleTogglePanel,
@@ -3,11 +3,14 @@ import { Store } from "@tauri-apps/plugin-store";
 import { useCallback, useEffect, useRef, useState } from "react";
 import { STORE_FILE } from "@/lib/config";
 import type { PersistedTab } from "@/lib/persisted-tabs";
-import type {
-  ClaudeActivityState,
-  Session,
-  SidebarPosition,
-  WorktreeLocation,
+import {
+  type ClaudeActivityState,
+  type ClaudeSession,
+  DEFAULT_BRANCH_TEMPLATE,
+  type Session,
+  type SidebarPosition,
+  type WorktreeFailurePolicy,
+  type WorktreeLocation,
 } from "@/lib/sessions";
 import type { NotificationSound } from "@/lib/sounds";
 
@@ -21,7 +24,9 @@ interface SessionStoreState {
   worktreeEnabled: boolean;
   worktreeLocation: WorktreeLocation;
   worktreeCustomPath: string;
-  branchPrefix: string;
+  worktreeFailurePolicy: WorktreeFailurePolicy;
+  worktreeInitSubmodules: boolean;
+  branchTemplate: string;
   notificationSound: NotificationSound;
   onboardingCompleted: boolean;
 }
@@ -36,7 +41,9 @@ const DEFAULT_STATE: SessionStoreState = {
   worktreeEnabled: true,
   worktreeLocation: "home",
   worktreeCustomPath: "",
-  branchPrefix: "",
+  worktreeFailurePolicy: "fallback",
+  worktreeInitSubmodules: true,
+  branchTemplate: DEFAULT_BRANCH_TEMPLATE,
   notificationSound: "chime",
   onboardingCompleted: false,
 };
@@ -64,6 +71,11 @@ export function useSessionStore() {
         : worktreePaths.map(() => true);
       const persistedTabs = rawTabs.filter((_, i) => !worktreePaths[i] || validFlags[i]);
 
+      // Conversations the restored tabs can resume
+      const claudeSessions = await invoke<Record<string, ClaudeSession>>("get_claude_sessions", {
+        sessionIds: persistedTabs.map((t) => t.id),
+      }).catch(() => ({}) as Record<string, ClaudeSession>);
+
       // Restore a session for each persisted tab
       // Session.id = PersistedTab.id — stable across restarts so
       // CLUTCH_SESSION_ID and status directories survive hot-reload / restart.
@@ -77,7 +89,8 @@ export function useSessionStore() {
         worktreePath: tab.worktreePath,
         gitRepoPath: tab.gitRepoPath,
         originalWorkingDir: tab.originalWorkingDir,
-        activityState: "idling" as const,
+        activityState: "idle" as const,
+        claudeSession: claudeSessions[tab.id],
       }));
       const activeSessionId = sessions.length > 0 ? sessions[0].id : null;
 
@@ -87,7 +100,15 @@ export function useSessionStore() {
       const worktreeEnabled = (await store.get<boolean>("worktreeEnabled")) ?? true;
       const worktreeLocation = (await store.get<WorktreeLocation>("worktreeLocation")) ?? "home";
       const worktreeCustomPath = (await store.get<string>("worktreeCustomPath")) ?? "";
-      const branchPrefix = (await store.get<string>("branchPrefix")) ?? "";
+      const worktreeFailurePolicy =
+        (await store.get<WorktreeFailurePolicy>("worktreeFailurePolicy")) ?? "fallback";
+      const worktreeInitSubmodules =
+        (await store.get<boolean>("worktreeInitSubmodules")) ?? true;
+      // Older versions stored only a prefix for the generated adjective-color-animal name
+      const legacyPrefix = await store.get<string>("branchPrefix");
+      const branchTemplate =
+        (await store.get<string>("branchTemplate")) ??
+        (legacyPrefix ? legacyPrefix + DEFAULT_BRANCH_TEMPLATE : DEFAULT_BRANCH_TEMPLATE);
       const notificationSound =
         (await store.get<NotificationSound>("notificationSound")) ?? "chime";
       const onboardingCompleted = (await store.get<boolean>("onboardingCompleted")) ?? false;
@@ -103,7 +124,9 @@ export function useSessionStore() {
           worktreeEnabled,
           worktreeLocation,
           worktreeCustomPath,
-          branchPrefix,
+          worktreeFailurePolicy,
+          worktreeInitSubmodules,
+          branchTemplate,
           notificationSound,
           onboardingCompleted,
         });
@@ -131,7 +154,9 @@ export function useSessionStore() {
       await store.set("worktreeEnabled", state.worktreeEnabled);
       await store.set("worktreeLocation", state.worktreeLocation);
       await store.set("worktreeCustomPath", state.worktreeCustomPath);
-      await store.set("branchPrefix", state.branchPrefix);
+      await store.set("worktreeFailurePolicy", state.worktreeFailurePolicy);
+      await store.set("worktreeInitSubmodules", state.worktreeInitSubmodules);
+      await store.set("branchTemplate", state.branchTemplate);
       await store.set("notificationSound", state.notificationSound);
       await store.set("onboardingCompleted", state.onboardingCompleted);
       await store.save();
@@ -193,8 +218,16 @@ export function useSessionStore() {
     setState((prev) => ({ ...prev, worktreeCustomPath: path }));
   }, []);
 
-  const setBranchPrefix = useCallback((prefix: string) => {
-    setState((prev) => ({ ...prev, branchPrefix: prefix }));
+  const setWorktreeFailurePolicy = useCallback((policy: WorktreeFailurePolicy) => {
+    setState((prev) => ({ ...prev, worktreeFailurePolicy: policy }));
+  }, []);
+
+  const setWorktreeInitSubmodules = useCallback((enabled: boolean) => {
+    setState((prev) => ({ ...prev, worktreeInitSubmodules: enabled }));
+  }, []);
+
+  const setBranchTemplate = useCallback((template: string) => {
+    setState((prev) => ({ ...prev, branchTemplate: template }));
   }, []);
 
   const setNotificationSound = useCallback((sound: NotificationSound) => {
@@ -247,7 +280,9 @@ export function useSessionStore() {
     setWorktreeEnabled,
     setWorktreeLocation,
     setWorktreeCustomPath,
-    setBranchPrefix,
+    setWorktreeFailurePolicy,
+    setWorktreeInitSubmodules,
+    setBranchTemplate,
     setNotificationSound,
     setOnboardingCompleted,
     setActivityState,
@@ -6,8 +6,7 @@ export const DEBUG_FORCE_UPDATE_TOAST = false;
 export const STORE_FILE = import.meta.env.DEV ? "sessions-dev.json" : "sessions.json";
 
 // Polling intervals (ms)
-export const ACTIVITY_POLL_INTERVAL = 250;
-export const GIT_BRANCH_POLL_INTERVAL = 5_000;
+export const OVERLAP_CHECK_INTERVAL = 30_000;
 
 // Update checker
 export const UPDATE_CHECK_DELAY = 3_000;
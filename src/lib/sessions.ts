@@ -1,14 +1,74 @@
-import { adjectives, animals, colors, uniqueNamesGenerator } from "unique-names-generator";
 import type { PersistedTab } from "./persisted-tabs";
 
 export type SessionStatus = "running" | "exited";
 
-export type ClaudeActivityState = "idling" | "running" | "finished" | "needs_input";
+/** Derived from hook events by the backend (see `SessionState` in activity.rs). */
+export type ClaudeActivityState =
+  | "idle"
+  | "working"
+  | "waiting_permission"
+  | "waiting_input"
+  | "compacting"
+  | "ended";
 
 export type SidebarPosition = "left" | "right" | "top" | "bottom";
 
 export type WorktreeLocation = "sibling" | "home" | "custom";
 
+export type WorktreeFailurePolicy = "fallback" | "retry" | "abort";
+
+/** What a session worktree checks out (see `WorktreeSource` in git/checkout.rs). */
+export type WorktreeSource =
+  | { kind: "new_branch" }
+  | { kind: "existing_branch"; branch: string }
+  | { kind: "detached"; rev: string };
+
+/** A repo file Clutch only acts on once the user trusts its content (see trust.rs). */
+export interface RepoFile {
+  name: string; // e.g. ".clutch/worktree-setup"
+  path: string;
+  description: string;
+  content: string;
+  trusted: boolean | null; // null until the user decides on this content
+}
+
+/** Branch name template used when none is configured (see git/branch_name.rs). */
+export const DEFAULT_BRANCH_TEMPLATE = "{adjective}-{color}-{animal}";
+
+export interface FileOverlap {
+  path: string;
+  hunks_overlap: boolean | null; // null when the sessions forked from different commits
+}
+
+export interface SessionOverlap {
+  session_a: string;
+  session_b: string;
+  files: FileOverlap[];
+}
+
+/** Latest hook event for a session (see activity.rs). */
+export interface SessionActivity {
+  event: string;
+  timestamp: number; // unix seconds
+  state: ClaudeActivityState; // after this event
+  tool_name: string | null;
+  command: string | null; // Bash command
+  file_path: string | null; // file a tool reads or edits
+  message: string | null;
+  notification_type: string | null;
+  permission_decision: "allow" | "deny" | "ask" | null; // set when .clutch/permissions answered
+  claude_session_id: string | null;
+  transcript_path: string | null;
+  cwd: string | null;
+}
+
+/** Claude conversation a session last prompted, kept across app restarts (see resume.rs). */
+export interface ClaudeSession {
+  session_id: string;
+  transcript_path: string | null;
+  updated_at: number; // unix seconds
+}
+
 export interface Session {
   id: string; // nanoid — same as PersistedTab.id, stable across restarts
   name: string;
@@ -17,20 +77,18 @@ export interface Session {
   status: SessionStatus;
   createdAt: number;
   activityState?: ClaudeActivityState;
+  activity?: SessionActivity;
+  permissionPending?: boolean; // the app can still answer the activity's PermissionRequest
+  claudeSession?: ClaudeSession;
+  resumeRequestedAt?: number; // set to relaunch the tab with `claude --resume`
   gitBranch?: string;
+  gitDirty?: boolean;
+  overlaps?: { sessionId: string; files: FileOverlap[] }[]; // files also edited by other sessions
   worktreePath?: string;
   gitRepoPath?: string;
   originalWorkingDir?: string;
 }
 
-export function generateBranchName(): string {
-  return uniqueNamesGenerator({
-    dictionaries: [adjectives, colors, animals],
-    separator: "-",
-    length: 3,
-  });
-}
-
 export function sessionDisplayName(session: Session, tab?: PersistedTab): string {
   if (session.name) return session.name;
   const dir =